@@ -0,0 +1,231 @@
+use crate::{constants::EPSILON, matrix::Matrix, ray::Ray, tuple::Tuple};
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Tuple,
+    pub max: Tuple,
+}
+
+impl Bounds {
+    pub fn new(min: Tuple, max: Tuple) -> Self {
+        Self { min, max }
+    }
+
+    /// A box that contains nothing. Adding a point or merging a box into it
+    /// yields that point or box.
+    pub fn empty() -> Self {
+        Self {
+            min: Tuple::point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Tuple::point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// Whether the box has a finite extent on every axis. Planes and
+    /// uncapped cylinders or cones are infinite.
+    pub fn is_finite(&self) -> bool {
+        [self.min, self.max]
+            .iter()
+            .all(|p| p.x.is_finite() && p.y.is_finite() && p.z.is_finite())
+    }
+
+    pub fn add_point(&mut self, point: Tuple) {
+        self.min = Tuple::point(
+            self.min.x.min(point.x),
+            self.min.y.min(point.y),
+            self.min.z.min(point.z),
+        );
+        self.max = Tuple::point(
+            self.max.x.max(point.x),
+            self.max.y.max(point.y),
+            self.max.z.max(point.z),
+        );
+    }
+
+    pub fn merge(mut self, other: &Bounds) -> Self {
+        if !other.is_empty() {
+            self.add_point(other.min);
+            self.add_point(other.max);
+        }
+
+        self
+    }
+
+    pub fn contains_point(&self, point: Tuple) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    /// Transform the box and return a new axis-aligned box containing all
+    /// eight transformed corners.
+    pub fn transform(&self, matrix: Matrix<4>) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+
+        let mut result = Bounds::empty();
+
+        for &x in &[self.min.x, self.max.x] {
+            for &y in &[self.min.y, self.max.y] {
+                for &z in &[self.min.z, self.max.z] {
+                    result.add_point(transform_corner(matrix, [x, y, z]));
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Check if the ray passes through the box.
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x, self.min.x, self.max.x);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y, self.min.y, self.max.y);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z, self.min.z, self.max.z);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        tmin <= tmax
+    }
+}
+
+/// Multiply a corner by the matrix, skipping zero entries so that infinite
+/// extents (planes, open cylinders) don't turn into `NaN`.
+fn transform_corner(matrix: Matrix<4>, corner: [f64; 3]) -> Tuple {
+    let row = |r: usize| {
+        (0..3)
+            .filter(|&c| matrix[r][c] != 0.)
+            .map(|c| matrix[r][c] * corner[c])
+            .sum::<f64>()
+            + matrix[r][3]
+    };
+
+    Tuple::point(row(0), row(1), row(2))
+}
+
+fn check_axis(origin: f64, direction: f64, min: f64, max: f64) -> (f64, f64) {
+    if direction.abs() < EPSILON {
+        // parallel to the slab, either always inside it or never
+        return if origin < min || origin > max {
+            (f64::INFINITY, f64::NEG_INFINITY)
+        } else {
+            (f64::NEG_INFINITY, f64::INFINITY)
+        };
+    }
+
+    let tmin = (min - origin) / direction;
+    let tmax = (max - origin) / direction;
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{matrix::Matrix, ray::Ray, tuple::Tuple};
+
+    use super::Bounds;
+
+    #[test]
+    fn adding_points_to_an_empty_bounding_box() {
+        let mut b = Bounds::empty();
+
+        assert!(b.is_empty());
+
+        b.add_point(Tuple::point(-5., 2., 0.));
+        b.add_point(Tuple::point(7., 0., -3.));
+
+        assert_eq!(b.min, Tuple::point(-5., 0., -3.));
+        assert_eq!(b.max, Tuple::point(7., 2., 0.));
+    }
+
+    #[test]
+    fn merging_two_bounding_boxes() {
+        let a = Bounds::new(Tuple::point(-5., -2., 0.), Tuple::point(7., 4., 4.));
+        let b = Bounds::new(Tuple::point(8., -7., -2.), Tuple::point(14., 2., 8.));
+
+        let merged = a.merge(&b);
+
+        assert_eq!(merged.min, Tuple::point(-5., -7., -2.));
+        assert_eq!(merged.max, Tuple::point(14., 4., 8.));
+    }
+
+    #[test]
+    fn checking_whether_a_box_contains_a_point() {
+        let b = Bounds::new(Tuple::point(5., -2., 0.), Tuple::point(11., 4., 7.));
+
+        assert!(b.contains_point(Tuple::point(5., -2., 0.)));
+        assert!(b.contains_point(Tuple::point(8., 1., 3.)));
+        assert!(!b.contains_point(Tuple::point(3., 0., 3.)));
+        assert!(!b.contains_point(Tuple::point(8., -4., 3.)));
+        assert!(!b.contains_point(Tuple::point(12., 1., 3.)));
+    }
+
+    #[test]
+    fn transforming_a_bounding_box() {
+        let b = Bounds::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.));
+        let m = Matrix::identity().rotation_y(PI / 4.).rotation_x(PI / 4.);
+
+        let t = b.transform(m);
+
+        assert_eq!(t.min, Tuple::point(-1.41421, -1.70711, -1.70711));
+        assert_eq!(t.max, Tuple::point(1.41421, 1.70711, 1.70711));
+    }
+
+    #[test]
+    fn transforming_an_infinite_bounding_box_keeps_finite_axes() {
+        let b = Bounds::new(
+            Tuple::point(f64::NEG_INFINITY, 0., f64::NEG_INFINITY),
+            Tuple::point(f64::INFINITY, 0., f64::INFINITY),
+        );
+
+        let t = b.transform(Matrix::identity().translation(0., 2., 0.));
+
+        assert_eq!(t.min.y, 2.);
+        assert_eq!(t.max.y, 2.);
+        assert_eq!(t.min.x, f64::NEG_INFINITY);
+        assert_eq!(t.max.z, f64::INFINITY);
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_bounding_box() {
+        let b = Bounds::new(Tuple::point(5., -2., 0.), Tuple::point(11., 4., 7.));
+
+        #[rustfmt::skip]
+        let examples = vec![
+            (Tuple::point(15.,  1.,  2.), Tuple::vector(-1.,  0.,  0.), true),
+            (Tuple::point(-5., -1.,  4.), Tuple::vector( 1.,  0.,  0.), true),
+            (Tuple::point( 7.,  6.,  5.), Tuple::vector( 0., -1.,  0.), true),
+            (Tuple::point( 9., -5.,  6.), Tuple::vector( 0.,  1.,  0.), true),
+            (Tuple::point( 8.,  2., 12.), Tuple::vector( 0.,  0., -1.), true),
+            (Tuple::point( 6.,  0., -5.), Tuple::vector( 0.,  0.,  1.), true),
+            (Tuple::point( 8.,  1., 3.5), Tuple::vector( 0.,  0.,  1.), true),
+            (Tuple::point( 9., -1., -8.), Tuple::vector( 2.,  4.,  6.), false),
+            (Tuple::point( 8.,  3., -4.), Tuple::vector( 6.,  2.,  4.), false),
+            (Tuple::point( 9., -1., -2.), Tuple::vector( 4.,  6.,  2.), false),
+            (Tuple::point( 4.,  0.,  9.), Tuple::vector( 0.,  0., -1.), false),
+            (Tuple::point( 8.,  6., -1.), Tuple::vector( 0., -1.,  0.), false),
+            (Tuple::point(12.,  5.,  4.), Tuple::vector(-1.,  0.,  0.), false),
+        ];
+
+        for (origin, direction, result) in examples.into_iter() {
+            let r = Ray::new(origin, direction.normalize());
+
+            assert_eq!(b.intersects(&r), result);
+        }
+    }
+}
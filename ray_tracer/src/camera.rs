@@ -1,34 +1,48 @@
-use crate::{canvas::Canvas, matrix::Matrix, ray::Ray, tuple::Tuple, world::World};
+use std::{collections::HashMap, f64::consts::PI};
 
-#[derive(Debug, PartialEq)]
+use crate::{
+    canvas::Canvas,
+    color::Color,
+    matrix::Matrix,
+    ray::Ray,
+    tuple::Tuple,
+    world::{ShadeResult, World},
+};
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Camera {
     hsize: usize,
     vsize: usize,
-    #[allow(dead_code)]
     field_of_view: f64,
     half_width: f64,
     half_height: f64,
     pixel_size: f64,
+    pixel_height: f64,
     transform: Matrix<4>,
+    antialiasing: usize,
+    aperture: f64,
+    focal_distance: f64,
+    max_depth: usize,
+    adaptive_aa: Option<(f64, usize)>,
+    clip_plane: Option<(Tuple, Tuple)>,
+    projection: Projection,
+}
+
+/// How a camera projects the world onto its image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// Rays spread out from the eye, objects shrink with distance.
+    Perspective,
+    /// Parallel rays, objects keep their size at any distance. The value is
+    /// the distance in world units from the center of the image to the edge
+    /// of its wider side.
+    Orthographic(f64),
 }
 
 impl Camera {
     pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
-        let half_view = (field_of_view / 2.).tan();
         let aspect = hsize as f64 / vsize as f64;
-
-        let half_width: f64;
-        let half_height: f64;
-
-        if aspect >= 1.0 {
-            half_width = half_view;
-            half_height = half_view / aspect;
-        } else {
-            half_width = half_view * aspect;
-            half_height = half_view;
-        }
-
-        let pixel_size = (half_width * 2.) / hsize as f64;
+        let (half_width, half_height) = Self::half_extents(field_of_view, aspect);
 
         Self {
             hsize,
@@ -36,8 +50,38 @@ impl Camera {
             field_of_view,
             half_width,
             half_height,
-            pixel_size,
+            pixel_size: (half_width * 2.) / hsize as f64,
+            pixel_height: (half_height * 2.) / vsize as f64,
             transform: Matrix::identity(),
+            antialiasing: 1,
+            aperture: 0.,
+            focal_distance: 1.,
+            max_depth: 10,
+            adaptive_aa: None,
+            clip_plane: None,
+            projection: Projection::Perspective,
+        }
+    }
+
+    /// Override the aspect ratio derived from `hsize / vsize`, keeping the
+    /// pixel dimensions. Useful for anamorphic renders or non-square pixels.
+    pub fn with_aspect(mut self, aspect: f64) -> Self {
+        let (half_width, half_height) = Self::half_extents(self.field_of_view, aspect);
+
+        self.half_width = half_width;
+        self.half_height = half_height;
+        self.pixel_size = (half_width * 2.) / self.hsize as f64;
+        self.pixel_height = (half_height * 2.) / self.vsize as f64;
+        self
+    }
+
+    fn half_extents(field_of_view: f64, aspect: f64) -> (f64, f64) {
+        let half_view = (field_of_view / 2.).tan();
+
+        if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
         }
     }
 
@@ -51,81 +95,594 @@ impl Camera {
         self.vsize
     }
 
+    /// Get a reference to the camera's field of view.
+    pub fn field_of_view(&self) -> f64 {
+        self.field_of_view
+    }
+
+    /// Get a reference to the camera's transform.
+    pub fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
     pub fn set_transform(mut self, transform: Matrix<4>) -> Self {
         self.transform = transform;
         self
     }
 
+    /// Point the camera down the z axis at the center of the world's bounds,
+    /// backing off until the box fits in view. Worlds without finite bounds
+    /// leave the camera unchanged.
+    pub fn frame(self, world: &World) -> Self {
+        let bounds = world.bounds();
+
+        if bounds.is_empty() {
+            return self;
+        }
+
+        let center = bounds.min + (bounds.max - bounds.min) * 0.5;
+        let radius = (bounds.max - bounds.min).magnitude() / 2.;
+        let half_view = self.half_width.min(self.half_height).atan();
+        let distance = radius / half_view.sin();
+
+        let from = center - Tuple::vector(0., 0., distance);
+        let up = Tuple::vector(0., 1., 0.);
+
+        self.set_transform(Matrix::identity().view_transform(from, center, up))
+    }
+
+    /// Get a reference to the camera's antialiasing grid size.
+    pub fn antialiasing(&self) -> usize {
+        self.antialiasing
+    }
+
+    /// Set the camera's antialiasing. Every pixel is sampled on an
+    /// `antialiasing x antialiasing` grid.
+    pub fn set_antialiasing(mut self, antialiasing: usize) -> Self {
+        self.antialiasing = antialiasing.max(1);
+        self
+    }
+
+    /// Number of rays traced per pixel.
+    pub fn samples(&self) -> usize {
+        self.antialiasing * self.antialiasing
+    }
+
+    /// Get a reference to the camera's aperture.
+    pub fn aperture(&self) -> f64 {
+        self.aperture
+    }
+
+    /// Set the camera's aperture, the diameter of the lens. Zero gives a
+    /// pinhole camera with everything in focus.
+    pub fn set_aperture(mut self, aperture: f64) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    /// Get a reference to the camera's focal distance.
+    pub fn focal_distance(&self) -> f64 {
+        self.focal_distance
+    }
+
+    /// Set the camera's focal distance, the distance to the plane in focus.
+    pub fn set_focal_distance(mut self, focal_distance: f64) -> Self {
+        self.focal_distance = focal_distance;
+        self
+    }
+
+    /// Get a reference to the camera's max recursion depth.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Set the camera's max depth of reflection and refraction rays.
+    pub fn set_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Get the camera's adaptive antialiasing threshold and sample limit.
+    pub fn adaptive_aa(&self) -> Option<(f64, usize)> {
+        self.adaptive_aa
+    }
+
+    /// Sample the corners of each pixel, shared with its neighbours, and
+    /// subdivide it only where the corner colors differ by more than
+    /// `threshold` in some channel, tracing at most `max_samples` rays per
+    /// pixel. Flat pixels average their corners, one ray per pixel. Like the
+    /// antialiasing grid it replaces, rays leave from points spread over the
+    /// lens.
+    pub fn set_adaptive_aa(mut self, threshold: f64, max_samples: usize) -> Self {
+        self.adaptive_aa = Some((threshold, max_samples));
+        self
+    }
+
+    /// Get the camera's clip plane as a point on the plane and its normal.
+    pub fn clip_plane(&self) -> Option<(Tuple, Tuple)> {
+        self.clip_plane
+    }
+
+    /// Discard everything on the side of the plane its normal points to, so
+    /// cutaway views reveal the interiors of objects. None renders normally.
+    pub fn set_clip_plane(mut self, clip_plane: Option<(Tuple, Tuple)>) -> Self {
+        self.clip_plane = clip_plane;
+        self
+    }
+
+    /// Get the camera's projection.
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// Switch between perspective and orthographic projection. Orthographic
+    /// cameras ignore the field of view and the aperture.
+    pub fn set_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
     pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
-        let xoffset = (px as f64 + 0.5) * self.pixel_size;
-        let yoffset = (py as f64 + 0.5) * self.pixel_size;
+        self.ray_for_pixel_offset(px, py, 0.5, 0.5)
+    }
+
+    /// Build a ray through the point `(dx, dy)` inside the pixel, where both
+    /// offsets are in `[0, 1)` and `(0, 0)` is the pixel's top left corner.
+    pub fn ray_for_pixel_offset(&self, px: usize, py: usize, dx: f64, dy: f64) -> Ray {
+        self.ray_for_sample(px as f64 + dx, py as f64 + dy, 0., 0.)
+    }
+
+    /// Build a ray through the canvas position `(x, y)`, measured in pixels,
+    /// leaving the lens at `(lens_x, lens_y)` in camera space.
+    fn ray_for_sample(&self, x: f64, y: f64, lens_x: f64, lens_y: f64) -> Ray {
+        let xoffset = x * self.pixel_size;
+        let yoffset = y * self.pixel_height;
 
         let world_x = self.half_width - xoffset;
         let world_y = self.half_height - yoffset;
 
         let inverse_transform = self.transform.inverse();
 
-        let pixel = inverse_transform * Tuple::point(world_x, world_y, -1.);
-        let origin = inverse_transform * Tuple::point(0., 0., 0.);
+        if let Projection::Orthographic(scale) = self.projection {
+            let scale = scale / self.half_width.max(self.half_height);
+            let origin = Tuple::point(world_x * scale, world_y * scale, 0.);
+
+            return Ray::new(
+                inverse_transform * origin,
+                (inverse_transform * Tuple::vector(0., 0., -1.)).normalize(),
+            );
+        }
+
+        let (pixel, origin) = if self.aperture > 0. {
+            let focus = self.focal_distance;
+
+            (
+                inverse_transform * Tuple::point(world_x * focus, world_y * focus, -focus),
+                inverse_transform * Tuple::point(lens_x, lens_y, 0.),
+            )
+        } else {
+            (
+                inverse_transform * Tuple::point(world_x, world_y, -1.),
+                inverse_transform * Tuple::point(0., 0., 0.),
+            )
+        };
         let direction = (pixel - origin).normalize();
 
         Ray::new(origin, direction)
     }
 
+    /// All rays traced for a pixel: an evenly spaced grid inside the pixel,
+    /// each leaving from a different point on the lens.
+    fn rays_for_pixel(&self, px: usize, py: usize) -> Vec<Ray> {
+        if self.antialiasing == 1 && self.aperture <= 0. {
+            return vec![self.ray_for_pixel(px, py)];
+        }
+
+        let n = self.antialiasing;
+        let samples = self.samples();
+
+        (0..samples)
+            .map(|k| {
+                let x = px as f64 + ((k % n) as f64 + 0.5) / n as f64;
+                let y = py as f64 + ((k / n) as f64 + 0.5) / n as f64;
+                let (lens_x, lens_y) = self.lens_offset(k, samples);
+
+                self.ray_for_sample(x, y, lens_x, lens_y)
+            })
+            .collect()
+    }
+
+    /// The point on the lens the `k`th of `samples` rays leaves from. The
+    /// points spread over the aperture disk in a spiral.
+    fn lens_offset(&self, k: usize, samples: usize) -> (f64, f64) {
+        let golden_angle = PI * (3. - 5.0_f64.sqrt());
+        let radius = (self.aperture / 2.) * ((k as f64 + 0.5) / samples as f64).sqrt();
+        let theta = k as f64 * golden_angle;
+
+        (radius * theta.cos(), radius * theta.sin())
+    }
+
+    fn color_at(&self, world: &World, ray: &Ray) -> Color {
+        self.shade(world, ray).color
+    }
+
+    fn shade(&self, world: &World, ray: &Ray) -> ShadeResult {
+        world.shade_ray_clipped(ray, self.max_depth, self.clip_plane)
+    }
+
+    fn color_for_pixel(&self, world: &World, px: usize, py: usize) -> Color {
+        self.shade_pixel(world, px, py).0
+    }
+
+    /// The color of a pixel together with the distance to the nearest hit of
+    /// its primary rays, infinity where they all miss. Adaptive antialiasing
+    /// traces the pixel's corners itself, `render` shares them between
+    /// neighbouring pixels.
+    fn shade_pixel(&self, world: &World, px: usize, py: usize) -> (Color, f64) {
+        let nearest =
+            |depth: f64, result: &ShadeResult| depth.min(result.t.unwrap_or(f64::INFINITY));
+
+        if self.adaptive_aa.is_some() {
+            let corners = [(px, py), (px + 1, py), (px, py + 1), (px + 1, py + 1)]
+                .map(|(x, y)| self.corner_sample(world, x, y));
+            let depth = corners.iter().fold(f64::INFINITY, nearest);
+            let colors = corners.map(|result| result.color);
+
+            return (self.adaptive_pixel(world, px, py, colors).0, depth);
+        }
+
+        let rays = self.rays_for_pixel(px, py);
+        let count = rays.len() as f64;
+
+        let (color, depth) = rays.iter().fold(
+            (Color::new_black(), f64::INFINITY),
+            |(color, depth), ray| {
+                let result = self.shade(world, ray);
+
+                (color + result.color.clone(), nearest(depth, &result))
+            },
+        );
+
+        (color * (1. / count), depth)
+    }
+
+    /// The adaptively antialiased color of a pixel from the colors at its
+    /// corners (top left, top right, bottom left, bottom right), together
+    /// with the rays it cost: one for its share of the corner grid plus the
+    /// rays traced inside it, at most `max_samples`.
+    fn adaptive_pixel(
+        &self,
+        world: &World,
+        px: usize,
+        py: usize,
+        corners: [Color; 4],
+    ) -> (Color, usize) {
+        let (x, y) = (px as f64, py as f64);
+        let mut samples: HashMap<(u64, u64), Color> =
+            [(x, y), (x + 1., y), (x, y + 1.), (x + 1., y + 1.)]
+                .iter()
+                .map(|&point| sample_key(point))
+                .zip(corners)
+                .collect();
+        let mut traced = 1;
+
+        let color = self.adaptive_cell(world, x, y, 1., &mut samples, &mut traced);
+
+        (color, traced)
+    }
+
+    /// Shade a cell from the samples at its corners, splitting it into four
+    /// where the corners differ and the sample budget allows. Samples are
+    /// keyed by position, so cells reuse the ones they share.
+    fn adaptive_cell(
+        &self,
+        world: &World,
+        x: f64,
+        y: f64,
+        size: f64,
+        samples: &mut HashMap<(u64, u64), Color>,
+        traced: &mut usize,
+    ) -> Color {
+        let (threshold, max_samples) = self.adaptive_aa.unwrap_or((0., 1));
+        let half = size / 2.;
+        let corners = [(x, y), (x + size, y), (x, y + size), (x + size, y + size)]
+            .map(|point| samples[&sample_key(point)].clone());
+        let differs = corners.iter().any(|c| {
+            (c.red() - corners[0].red()).abs() > threshold
+                || (c.green() - corners[0].green()).abs() > threshold
+                || (c.blue() - corners[0].blue()).abs() > threshold
+        });
+        let new_points: Vec<(f64, f64)> = [
+            (x + half, y),
+            (x, y + half),
+            (x + half, y + half),
+            (x + size, y + half),
+            (x + half, y + size),
+        ]
+        .iter()
+        .copied()
+        .filter(|&point| !samples.contains_key(&sample_key(point)))
+        .collect();
+
+        if !differs || *traced + new_points.len() > max_samples {
+            return corners
+                .iter()
+                .fold(Color::new_black(), |color, c| color + c.clone() * 0.25);
+        }
+
+        for (sx, sy) in new_points {
+            let color = self.adaptive_sample(world, sx, sy, traced);
+
+            samples.insert(sample_key((sx, sy)), color);
+        }
+
+        [(x, y), (x + half, y), (x, y + half), (x + half, y + half)]
+            .iter()
+            .map(|&(x, y)| self.adaptive_cell(world, x, y, half, samples, traced))
+            .fold(Color::new_black(), |color, c| color + c * 0.25)
+    }
+
+    /// Trace one adaptive sample inside a pixel, each leaving from the next
+    /// point of the lens spiral that `render` uses for `max_samples` rays.
+    fn adaptive_sample(&self, world: &World, x: f64, y: f64, traced: &mut usize) -> Color {
+        let (_, max_samples) = self.adaptive_aa.unwrap_or((0., 1));
+        let samples = max_samples.max(1);
+        let (lens_x, lens_y) = self.lens_offset(*traced % samples, samples);
+
+        *traced += 1;
+
+        self.color_at(world, &self.ray_for_sample(x, y, lens_x, lens_y))
+    }
+
+    /// Trace the pixel corner `(x, y)`. The four corners of a pixel leave
+    /// from four different points on the lens.
+    fn corner_sample(&self, world: &World, x: usize, y: usize) -> ShadeResult {
+        let (lens_x, lens_y) = self.lens_offset(x % 2 + 2 * (y % 2), 4);
+
+        self.shade(
+            world,
+            &self.ray_for_sample(x as f64, y as f64, lens_x, lens_y),
+        )
+    }
+
+    /// Render with adaptive antialiasing, tracing the grid of pixel corners
+    /// a row at a time so neighbouring pixels share their corner samples.
+    fn render_adaptive_into(&self, world: &World, canvas: &mut Canvas) {
+        let corner_row =
+            |y: usize| Self::map_columns(self.hsize + 1, |x| self.corner_sample(world, x, y).color);
+        let mut top = corner_row(0);
+
+        for y in 0..self.vsize {
+            let bottom = corner_row(y + 1);
+            let colors = Self::map_columns(self.hsize, |x| {
+                let corners = [
+                    top[x].clone(),
+                    top[x + 1].clone(),
+                    bottom[x].clone(),
+                    bottom[x + 1].clone(),
+                ];
+
+                self.adaptive_pixel(world, x, y, corners).0
+            });
+
+            for (x, color) in colors.iter().enumerate() {
+                canvas.set(x, y, color);
+            }
+
+            top = bottom;
+        }
+    }
+
     #[cfg(feature = "parallel")]
-    pub fn render(&self, world: World) -> Canvas {
+    fn map_columns<F>(count: usize, f: F) -> Vec<Color>
+    where
+        F: Fn(usize) -> Color + Sync + Send,
+    {
         use rayon::prelude::*;
-        use std::sync::{Arc, Mutex};
 
-        fn unwrap_arc_mutex<T: Default>(x: Arc<Mutex<T>>) -> T {
-            std::mem::take(&mut x.lock().unwrap())
+        (0..count).into_par_iter().map(f).collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn map_columns<F>(count: usize, f: F) -> Vec<Color>
+    where
+        F: Fn(usize) -> Color,
+    {
+        (0..count).map(f).collect()
+    }
+
+    pub fn render(&self, world: &World) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+
+        self.render_into(world, &mut image);
+
+        image
+    }
+
+    /// Render the world together with a depth map. The depth map holds, row by
+    /// row, the distance to the nearest hit among the rays shading each pixel,
+    /// after the clip plane, or infinity where they all miss.
+    pub fn render_with_depth(&self, world: &World) -> (Canvas, Vec<f64>) {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let mut depth = Vec::with_capacity(self.hsize * self.vsize);
+
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let (color, distance) = self.shade_pixel(world, x, y);
+
+                image.set(x, y, &color);
+                depth.push(distance);
+            }
         }
 
-        let image = Arc::new(Mutex::new(Canvas::new(self.hsize, self.vsize)));
+        (image, depth)
+    }
+
+    /// Render each term of the shading into its own canvas (arbitrary output
+    /// variables): `ambient`, `diffuse`, `specular`, `environment`,
+    /// `reflection`, `refraction` and `background`, which add up to the
+    /// beauty render, and one pass per light, `light_0`, with its diffuse and
+    /// specular light. The clip plane is not applied.
+    pub fn render_aovs(&self, world: &World) -> HashMap<String, Canvas> {
+        const PASSES: [&str; 8] = [
+            "ambient",
+            "diffuse",
+            "specular",
+            "environment",
+            "reflection",
+            "refraction",
+            "background",
+            "light_0",
+        ];
+        let mut passes: HashMap<String, Canvas> = PASSES
+            .iter()
+            .map(|name| (name.to_string(), Canvas::new(self.hsize, self.vsize)))
+            .collect();
+
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let rays = self.rays_for_pixel(x, y);
+                let weight = 1. / rays.len() as f64;
+
+                for ray in rays.iter() {
+                    let terms = world.shade_terms(ray, self.max_depth);
+                    let light = terms.diffuse.clone() + terms.specular.clone();
+                    let colors = vec![
+                        terms.ambient,
+                        terms.diffuse,
+                        terms.specular,
+                        terms.environment,
+                        terms.reflection,
+                        terms.refraction,
+                        terms.background,
+                        light,
+                    ];
+
+                    for (name, color) in PASSES.iter().zip(colors) {
+                        let canvas = passes.get_mut(*name).unwrap();
+                        let sum = canvas.get(x, y).clone() + color * weight;
+
+                        canvas.set(x, y, &sum);
+                    }
+                }
+            }
+        }
+
+        passes
+    }
+
+    /// Render only the objects tagged `tag`, see `World::set_layer`.
+    pub fn render_layer(&self, world: &World, tag: &str) -> Canvas {
+        self.render(&world.clone().set_layer(Some(tag.to_string())))
+    }
+
+    /// Render a quick preview first and refine it. The image is traced at 1/8,
+    /// 1/4, 1/2 and full resolution, with each traced pixel filling its block,
+    /// and `callback` sees the canvas after every level. Pixels traced at a
+    /// coarser level are reused.
+    pub fn render_progressive_resolution<F>(&self, world: &World, callback: &mut F)
+    where
+        F: FnMut(&Canvas),
+    {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+
+        for step in [8, 4, 2, 1] {
+            for y in (0..self.vsize).step_by(step) {
+                for x in (0..self.hsize).step_by(step) {
+                    let traced = step < 8 && x % (step * 2) == 0 && y % (step * 2) == 0;
+                    let color = if traced {
+                        image.get(x, y).clone()
+                    } else {
+                        self.color_for_pixel(world, x, y)
+                    };
+
+                    for by in y..(y + step).min(self.vsize) {
+                        for bx in x..(x + step).min(self.hsize) {
+                            image.set(bx, by, &color);
+                        }
+                    }
+                }
+            }
+
+            callback(&image);
+        }
+    }
+
+    /// Render into an existing canvas, reusing its buffer instead of
+    /// allocating a new one every frame. The canvas must match the camera size.
+    #[cfg(feature = "parallel")]
+    pub fn render_into(&self, world: &World, canvas: &mut Canvas) {
+        use rayon::prelude::*;
+        use std::sync::Mutex;
+
+        self.assert_canvas_size(canvas);
+
+        if self.adaptive_aa.is_some() {
+            return self.render_adaptive_into(world, canvas);
+        }
+
+        let image = Mutex::new(canvas);
 
         for y in 0..self.vsize {
             (0..self.hsize).into_par_iter().for_each(|x| {
-                let ray = self.ray_for_pixel(x, y);
-                let color = world.color_at(&ray, 10);
+                let color = self.color_for_pixel(world, x, y);
 
                 image.lock().unwrap().set(x, y, &color);
             })
         }
-
-        unwrap_arc_mutex(image)
     }
 
+    /// Render into an existing canvas, reusing its buffer instead of
+    /// allocating a new one every frame. The canvas must match the camera size.
     #[cfg(not(feature = "parallel"))]
-    pub fn render(&self, world: World) -> Canvas {
-        let mut image = Canvas::new(self.hsize, self.vsize);
+    pub fn render_into(&self, world: &World, canvas: &mut Canvas) {
+        self.assert_canvas_size(canvas);
+
+        if self.adaptive_aa.is_some() {
+            return self.render_adaptive_into(world, canvas);
+        }
 
         for y in 0..self.vsize {
             for x in 0..self.hsize {
-                let ray = self.ray_for_pixel(x, y);
-                let color = world.color_at(&ray, 10);
+                let color = self.color_for_pixel(world, x, y);
 
-                image.set(x, y, &color);
+                canvas.set(x, y, &color);
             }
         }
+    }
 
-        image
+    fn assert_canvas_size(&self, canvas: &Canvas) {
+        assert!(
+            canvas.width == self.hsize && canvas.height == self.vsize,
+            "canvas is {}x{} but the camera renders {}x{}",
+            canvas.width,
+            canvas.height,
+            self.hsize,
+            self.vsize
+        );
     }
 }
 
+/// A sample position as a map key. Subdivision only halves cells, so equal
+/// positions are always bit-for-bit equal.
+fn sample_key((x, y): (f64, f64)) -> (u64, u64) {
+    (x.to_bits(), y.to_bits())
+}
+
 #[cfg(test)]
 mod tests {
     use std::f64::consts::PI;
 
+    use crate::canvas::Canvas;
     use crate::color::Color;
     use crate::light::Light;
-    use crate::material::Material;
     use crate::matrix::Matrix;
     use crate::shapes::sphere::Sphere;
     use crate::tuple::Tuple;
     use crate::utils::fuzzy_equal::fuzzy_equal;
     use crate::world::World;
 
-    use super::Camera;
+    use super::{Camera, Projection};
 
     #[test]
     fn constructing_a_camera() {
@@ -155,6 +712,31 @@ mod tests {
         assert!(fuzzy_equal(c.pixel_size, 0.01));
     }
 
+    #[test]
+    fn overriding_the_aspect_ratio_keeps_the_pixel_dimensions() {
+        let c = Camera::new(200, 100, PI / 2.);
+        let wide = Camera::new(200, 100, PI / 2.).with_aspect(4.);
+
+        assert!(fuzzy_equal(c.half_width, 1.));
+        assert!(fuzzy_equal(wide.half_width, 1.));
+        assert!(fuzzy_equal(c.half_height, 0.5));
+        assert!(fuzzy_equal(wide.half_height, 0.25));
+        assert_eq!(wide.hsize, 200);
+        assert_eq!(wide.vsize, 100);
+        assert!(fuzzy_equal(wide.pixel_size, 0.01));
+        assert!(fuzzy_equal(wide.pixel_height, 0.005));
+    }
+
+    #[test]
+    fn overriding_the_aspect_ratio_on_a_vertical_canvas_changes_half_width() {
+        let c = Camera::new(100, 200, PI / 2.).with_aspect(0.25);
+
+        assert!(fuzzy_equal(c.half_width, 0.25));
+        assert!(fuzzy_equal(c.half_height, 1.));
+        assert_eq!(c.hsize, 100);
+        assert_eq!(c.vsize, 200);
+    }
+
     #[test]
     fn constructing_a_ray_through_the_center_of_the_canvas() {
         let c = Camera::new(201, 101, PI / 2.);
@@ -173,6 +755,32 @@ mod tests {
         assert_eq!(r.direction, Tuple::vector(0.66519, 0.33259, -0.66851));
     }
 
+    #[test]
+    fn constructing_a_ray_through_an_offset_in_the_pixel() {
+        let c = Camera::new(201, 101, PI / 2.);
+        let center = c.ray_for_pixel(0, 0);
+        let corner = c.ray_for_pixel_offset(0, 0, 0., 0.);
+
+        let offset_center = c.ray_for_pixel_offset(0, 0, 0.5, 0.5);
+
+        assert_eq!(offset_center.origin, center.origin);
+        assert_eq!(offset_center.direction, center.direction);
+        assert!(corner.direction.x > center.direction.x);
+        assert!(corner.direction.y > center.direction.y);
+    }
+
+    #[test]
+    fn an_orthographic_camera_casts_parallel_rays() {
+        let c = Camera::new(201, 101, PI / 2.).set_projection(Projection::Orthographic(2.));
+        let center = c.ray_for_pixel(100, 50);
+        let corner = c.ray_for_pixel_offset(0, 0, 0., 0.);
+
+        assert_eq!(center.origin, Tuple::point(0., 0., 0.));
+        assert_eq!(center.direction, Tuple::vector(0., 0., -1.));
+        assert_eq!(corner.origin, Tuple::point(2., 2. * 101. / 201., 0.));
+        assert_eq!(corner.direction, Tuple::vector(0., 0., -1.));
+    }
+
     #[test]
     fn constructing_a_ray_when_the_camera_is_transformed() {
         let c = Camera::new(201, 101, PI / 2.).set_transform(
@@ -190,22 +798,48 @@ mod tests {
         );
     }
 
-    fn default_world() -> World {
-        let light = Light::new(Tuple::point(-10., 10., -10.), Color::new(1., 1., 1.));
-        let s1 = Sphere::default().set_material(
-            Material::default()
-                .set_color(Color::new(0.8, 1.0, 0.6))
-                .set_diffuse(0.7)
-                .set_specular(0.2),
-        );
-        let s2 = Sphere::default().set_transform(Matrix::identity().scaling(0.5, 0.5, 0.5));
+    #[test]
+    fn the_default_camera_uses_a_single_sample_and_a_pinhole() {
+        let c = Camera::new(160, 120, PI / 2.);
 
-        World::new(Some(light), vec![Box::new(s1), Box::new(s2)])
+        assert_eq!(c.samples(), 1);
+        assert_eq!(c.aperture(), 0.);
+        assert_eq!(c.max_depth(), 10);
+    }
+
+    #[test]
+    fn antialiasing_traces_a_grid_of_rays_per_pixel() {
+        let c = Camera::new(201, 101, PI / 2.).set_antialiasing(3);
+
+        let rays = c.rays_for_pixel(100, 50);
+
+        assert_eq!(c.samples(), 9);
+        assert_eq!(rays.len(), 9);
+        // the middle sample passes through the center of the pixel
+        assert_eq!(rays[4].direction, Tuple::vector(0., 0., -1.));
+        assert!(rays[0].direction.x > 0. && rays[0].direction.y > 0.);
+    }
+
+    #[test]
+    fn rays_through_an_aperture_converge_on_the_focal_plane() {
+        let c = Camera::new(201, 101, PI / 2.)
+            .set_antialiasing(2)
+            .set_aperture(0.5)
+            .set_focal_distance(4.);
+
+        for ray in c.rays_for_pixel(100, 50) {
+            let t = -4. / ray.direction.z;
+            let p = ray.position(t);
+
+            assert!(ray.origin.x != 0. || ray.origin.y != 0.);
+            assert!(p.x.abs() < c.pixel_size * 4.);
+            assert!(p.y.abs() < c.pixel_height * 4.);
+        }
     }
 
     #[test]
     fn rendering_a_world_with_a_camera() {
-        let w = default_world();
+        let w = World::demo();
 
         let from = Tuple::point(0., 0., -5.);
         let to = Tuple::point(0., 0., 0.);
@@ -213,8 +847,240 @@ mod tests {
         let c = Camera::new(11, 11, PI / 2.)
             .set_transform(Matrix::identity().view_transform(from, to, up));
 
-        let image = c.render(w);
+        let image = c.render(&w);
 
         assert_eq!(image.get(5, 5), &Color::new(0.38066, 0.47583, 0.2855));
     }
+
+    #[test]
+    fn a_clip_plane_reveals_the_inside_of_a_sphere() {
+        let w = World::new(
+            Some(Light::new(
+                Tuple::point(-10., 10., -10.),
+                Color::new_white(),
+            )),
+            vec![Box::new(Sphere::default())],
+        );
+        let c = Camera::new(11, 11, PI / 2.).set_transform(Matrix::identity().view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        ));
+        let clipped = c
+            .clone()
+            .set_clip_plane(Some((Tuple::point(0., 0., 0.), Tuple::vector(0., 0., -1.))));
+
+        // the back wall faces away from the light, leaving only ambient
+        let ambient = Color::new(0.1, 0.1, 0.1);
+
+        assert_ne!(c.render(&w).get(5, 5), &ambient);
+        assert_eq!(clipped.render(&w).get(5, 5), &ambient);
+    }
+
+    #[test]
+    fn the_lighting_passes_add_up_to_the_render() {
+        let w = World::demo();
+        let c = Camera::new(11, 11, PI / 2.).set_transform(Matrix::identity().view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        ));
+
+        let image = c.render(&w);
+        let passes = c.render_aovs(&w);
+
+        image.for_each(|x, y| {
+            let sum = passes["ambient"].get(x, y).clone()
+                + passes["diffuse"].get(x, y).clone()
+                + passes["specular"].get(x, y).clone();
+
+            assert_eq!(&sum, image.get(x, y));
+            assert_eq!(
+                passes["light_0"].get(x, y),
+                &(passes["diffuse"].get(x, y).clone() + passes["specular"].get(x, y).clone())
+            );
+        });
+        assert_eq!(passes["ambient"].get(5, 5), &Color::new(0.08, 0.1, 0.06));
+    }
+
+    #[test]
+    fn rendering_into_a_preallocated_canvas_matches_render() {
+        let w = World::demo();
+        let c = Camera::new(11, 11, PI / 2.).set_transform(Matrix::identity().view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        ));
+        let mut canvas = Canvas::new_with_color(11, 11, Color::new(1., 0., 0.));
+
+        c.render_into(&w, &mut canvas);
+
+        assert!(canvas.rows().eq(c.render(&w).rows()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn rendering_into_a_canvas_of_the_wrong_size_panics() {
+        let w = World::demo();
+        let c = Camera::new(11, 11, PI / 2.);
+        let mut canvas = Canvas::new(10, 11);
+
+        c.render_into(&w, &mut canvas);
+    }
+
+    #[test]
+    fn rendering_a_depth_map() {
+        let w = World::demo();
+        let c = Camera::new(11, 11, PI / 2.).set_transform(Matrix::identity().view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        ));
+
+        let (image, depth) = c.render_with_depth(&w);
+
+        assert_eq!(depth.len(), image.width * image.height);
+        assert!(fuzzy_equal(depth[5 * 11 + 5], 4.));
+        assert_eq!(depth[0], f64::INFINITY);
+    }
+
+    #[test]
+    fn the_depth_map_matches_the_clipped_render() {
+        let w = World::new(
+            Some(Light::new(
+                Tuple::point(-10., 10., -10.),
+                Color::new_white(),
+            )),
+            vec![Box::new(Sphere::default())],
+        );
+        let c = Camera::new(11, 11, PI / 2.)
+            .set_transform(Matrix::identity().view_transform(
+                Tuple::point(0., 0., -5.),
+                Tuple::point(0., 0., 0.),
+                Tuple::vector(0., 1., 0.),
+            ))
+            .set_antialiasing(2)
+            .set_clip_plane(Some((Tuple::point(0., 0., 0.), Tuple::vector(0., 0., -1.))));
+
+        let (image, depth) = c.render_with_depth(&w);
+
+        // the front of the sphere is clipped away, the back wall is 6 away
+        assert_eq!(image, c.render(&w));
+        assert!(depth[5 * 11 + 5] > 5.9 && depth[5 * 11 + 5] <= 6.);
+        assert_eq!(depth[0], f64::INFINITY);
+    }
+
+    #[test]
+    fn progressive_rendering_refines_towards_the_full_image() {
+        let w = World::demo();
+        let c = Camera::new(11, 11, PI / 2.).set_transform(Matrix::identity().view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        ));
+        let full = c.render(&w);
+        let mut mismatches = vec![];
+
+        c.render_progressive_resolution(&w, &mut |canvas: &Canvas| {
+            let rows = canvas.rows().zip(full.rows());
+            let count = rows
+                .flat_map(|(a, b)| a.iter().zip(b.iter()))
+                .filter(|(a, b)| a != b)
+                .count();
+
+            mismatches.push(count);
+        });
+
+        assert_eq!(mismatches.len(), 4);
+        assert!(mismatches.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(mismatches[0] > 0);
+        assert_eq!(mismatches[3], 0);
+    }
+
+    #[test]
+    fn rendering_a_layer_shows_only_the_tagged_objects() {
+        let light = Light::new(Tuple::point(-10., 10., -10.), Color::new_white());
+        let a = Sphere::default()
+            .with_transform(Matrix::identity().translation(-1.5, 0., 0.))
+            .with_tag("a");
+        let b = Sphere::default()
+            .with_transform(Matrix::identity().translation(1.5, 0., 0.))
+            .with_tag("b");
+        let w = World::new(Some(light), vec![Box::new(a), Box::new(b)]);
+        let c = Camera::new(11, 11, PI / 2.).set_transform(Matrix::identity().view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        ));
+
+        let image = c.render_layer(&w, "a");
+
+        assert_ne!(image.get(3, 5), &Color::new_black());
+        assert_eq!(image.get(7, 5), &Color::new_black());
+        assert_eq!(w.layer(), None);
+        assert_eq!(w.objects().len(), 2);
+    }
+
+    #[test]
+    fn framing_a_world_fits_its_bounds_in_view() {
+        let left = Sphere::default().with_transform(Matrix::identity().translation(-2., 0., 0.));
+        let right = Sphere::default().with_transform(Matrix::identity().translation(2., 0., 0.));
+        let w = World::new(None, vec![Box::new(left), Box::new(right)]);
+
+        let c = Camera::new(11, 11, PI / 2.).frame(&w);
+
+        assert!(w.pick(&c.ray_for_pixel(2, 5)).is_some());
+        assert!(w.pick(&c.ray_for_pixel(8, 5)).is_some());
+        assert!(w.pick(&c.ray_for_pixel(0, 5)).is_none());
+        assert!(w.pick(&c.ray_for_pixel(10, 5)).is_none());
+    }
+
+    #[test]
+    fn adaptive_antialiasing_keeps_the_depth_of_field() {
+        let w = World::demo();
+        let c = Camera::new(11, 11, PI / 2.)
+            .set_transform(Matrix::identity().view_transform(
+                Tuple::point(0., 0., -5.),
+                Tuple::point(0., 0., 0.),
+                Tuple::vector(0., 1., 0.),
+            ))
+            .set_adaptive_aa(0.01, 16);
+        let blurred = c.clone().set_aperture(0.5).set_focal_distance(2.);
+
+        assert_ne!(
+            blurred.color_for_pixel(&w, 6, 5),
+            c.color_for_pixel(&w, 6, 5)
+        );
+    }
+
+    #[test]
+    fn adaptive_antialiasing_only_subdivides_edges() {
+        let w = World::demo();
+        let transform = Matrix::identity().view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+        let c = Camera::new(11, 11, PI / 2.)
+            .set_transform(transform)
+            .set_adaptive_aa(0.01, 16);
+        let plain = Camera::new(11, 11, PI / 2.).set_transform(transform);
+        let brute = plain.clone().set_antialiasing(4);
+
+        let corners = |px: usize, py: usize| {
+            [(px, py), (px + 1, py), (px, py + 1), (px + 1, py + 1)]
+                .map(|(x, y)| c.corner_sample(&w, x, y).color)
+        };
+        let (flat, flat_samples) = c.adaptive_pixel(&w, 0, 0, corners(0, 0));
+        let (edge, edge_samples) = c.adaptive_pixel(&w, 6, 5, corners(6, 5));
+        let expected = brute.color_for_pixel(&w, 6, 5);
+
+        assert_eq!(flat_samples, 1);
+        assert_eq!(flat, plain.color_for_pixel(&w, 0, 0));
+        assert!(edge_samples > 1 && edge_samples <= 16);
+        assert_eq!(c.render(&w).get(6, 5), &edge);
+        assert!((edge.red() - expected.red()).abs() < 0.05);
+        assert!((edge.green() - expected.green()).abs() < 0.05);
+        assert!((edge.blue() - expected.blue()).abs() < 0.05);
+    }
 }
@@ -1,6 +1,23 @@
+use std::{error::Error, fmt};
+
 use crate::color::{Color, RGB};
 
-#[derive(Debug, Default)]
+/// Returned when a pixel coordinate lies outside of the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfBounds {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pixel ({}, {}) is outside of the canvas", self.x, self.y)
+    }
+}
+
+impl Error for OutOfBounds {}
+
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
@@ -24,6 +41,176 @@ impl Canvas {
         }
     }
 
+    /// Set every pixel to the given color.
+    pub fn fill(&mut self, color: &Color) {
+        self.pixels.fill(color.clone());
+    }
+
+    /// Grade every pixel, see `Color::adjust`.
+    pub fn adjust(&mut self, brightness: f64, saturation: f64, contrast: f64) {
+        for pixel in self.pixels.iter_mut() {
+            *pixel = pixel.adjust(brightness, saturation, contrast);
+        }
+    }
+
+    /// Bring every pixel into range with `Color::clamp_preserve_hue`, so that
+    /// the per-channel clamping of the output formats doesn't shift hues.
+    pub fn clamp_preserve_hue(&mut self) {
+        for pixel in self.pixels.iter_mut() {
+            *pixel = pixel.clamp_preserve_hue();
+        }
+    }
+
+    /// Scale every pixel so the log-average luminance of the canvas becomes
+    /// middle gray (a key of 0.18), like a camera's auto exposure. Bright
+    /// scenes are darkened and dim ones brightened before output clamps them.
+    pub fn auto_exposure(&mut self) {
+        const KEY: f64 = 0.18;
+        // keeps black pixels from sending the log to negative infinity
+        const DELTA: f64 = 1e-4;
+
+        if self.pixels.is_empty() {
+            return;
+        }
+
+        let log_sum: f64 = self
+            .pixels
+            .iter()
+            .map(|pixel| (DELTA + pixel.luminance().max(0.)).ln())
+            .sum();
+        let log_average = (log_sum / self.pixels.len() as f64).exp();
+        let exposure = KEY / log_average;
+
+        for pixel in self.pixels.iter_mut() {
+            *pixel = pixel.clone() * exposure;
+        }
+    }
+
+    /// The per-pixel absolute difference to another canvas of the same size.
+    pub fn difference(&self, other: &Canvas) -> Canvas {
+        self.assert_same_size(other);
+
+        let pixels = self
+            .pixels
+            .iter()
+            .zip(other.pixels.iter())
+            .map(|(a, b)| {
+                Color::new(
+                    (a.red() - b.red()).abs(),
+                    (a.green() - b.green()).abs(),
+                    (a.blue() - b.blue()).abs(),
+                )
+            })
+            .collect();
+
+        Canvas {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
+    }
+
+    /// The peak signal-to-noise ratio to another canvas of the same size in
+    /// decibels, taking 1 as the peak channel value. Identical canvases give
+    /// infinity, and lower values mean larger differences.
+    pub fn psnr(&self, other: &Canvas) -> f64 {
+        let difference = self.difference(other);
+        let squared_error: f64 = difference
+            .pixels
+            .iter()
+            .map(|d| d.red().powi(2) + d.green().powi(2) + d.blue().powi(2))
+            .sum();
+        let mse = squared_error / (self.pixels.len() * 3) as f64;
+
+        if mse == 0. {
+            return f64::INFINITY;
+        }
+
+        -10. * mse.log10()
+    }
+
+    /// A copy with the pixels whose depth lies within `tolerance` of the
+    /// `focal` distance tinted red, to show what a depth of field render keeps
+    /// sharp. `depth` is a depth map as from `Camera::render_with_depth`.
+    pub fn focus_peaking(&self, depth: &[f64], focal: f64, tolerance: f64) -> Canvas {
+        assert_eq!(
+            depth.len(),
+            self.pixels.len(),
+            "depth map doesn't match the canvas size"
+        );
+
+        let highlight = Color::new(1., 0., 0.);
+        let pixels = self
+            .pixels
+            .iter()
+            .zip(depth)
+            .map(|(pixel, d)| {
+                if (d - focal).abs() <= tolerance {
+                    (pixel.clone() + highlight.clone()) * 0.5
+                } else {
+                    pixel.clone()
+                }
+            })
+            .collect();
+
+        Canvas {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
+    }
+
+    /// A copy with the rows in reverse order, e.g. for bottom-left origins.
+    pub fn flip_vertical(&self) -> Canvas {
+        self.remap(self.width, self.height, |x, y| (x, self.height - 1 - y))
+    }
+
+    /// A copy with every row mirrored left to right.
+    pub fn flip_horizontal(&self) -> Canvas {
+        self.remap(self.width, self.height, |x, y| (self.width - 1 - x, y))
+    }
+
+    /// A copy turned a quarter turn, swapping width and height.
+    pub fn rotate_90(&self, clockwise: bool) -> Canvas {
+        if clockwise {
+            self.remap(self.height, self.width, |x, y| (y, self.height - 1 - x))
+        } else {
+            self.remap(self.height, self.width, |x, y| (self.width - 1 - y, x))
+        }
+    }
+
+    /// A `width` by `height` canvas whose pixel at `(x, y)` is this canvas's
+    /// pixel at `source(x, y)`.
+    fn remap<F>(&self, width: usize, height: usize, source: F) -> Canvas
+    where
+        F: Fn(usize, usize) -> (usize, usize),
+    {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let (source_x, source_y) = source(x, y);
+                self.get(source_x, source_y).clone()
+            })
+            .collect();
+
+        Canvas {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn assert_same_size(&self, other: &Canvas) {
+        assert!(
+            self.width == other.width && self.height == other.height,
+            "canvas is {}x{} but the other is {}x{}",
+            self.width,
+            self.height,
+            other.width,
+            other.height
+        );
+    }
+
     pub fn get(&self, x: usize, y: usize) -> &Color {
         &self.pixels[self.get_pixel_index(x, y)]
     }
@@ -33,6 +220,23 @@ impl Canvas {
         self.pixels[index] = color.clone();
     }
 
+    /// Like `get`, but returns an error instead of panicking for coordinates
+    /// outside of the canvas.
+    pub fn try_get(&self, x: usize, y: usize) -> Result<&Color, OutOfBounds> {
+        let index = self.checked_pixel_index(x, y)?;
+
+        Ok(&self.pixels[index])
+    }
+
+    /// Like `set`, but returns an error instead of writing into a neighboring
+    /// row or panicking for coordinates outside of the canvas.
+    pub fn try_set(&mut self, x: usize, y: usize, color: &Color) -> Result<(), OutOfBounds> {
+        let index = self.checked_pixel_index(x, y)?;
+        self.pixels[index] = color.clone();
+
+        Ok(())
+    }
+
     pub fn set_center(&mut self, x: usize, y: usize, color: &Color) {
         let index = self.get_pixel_index(x + self.width / 2, self.height / 2 - y);
         self.pixels[index] = color.clone();
@@ -42,6 +246,14 @@ impl Canvas {
         y * self.width + x
     }
 
+    fn checked_pixel_index(&self, x: usize, y: usize) -> Result<usize, OutOfBounds> {
+        if x < self.width && y < self.height {
+            Ok(self.get_pixel_index(x, y))
+        } else {
+            Err(OutOfBounds { x, y })
+        }
+    }
+
     pub fn for_each<F>(&self, func: F)
     where
         F: Fn(usize, usize),
@@ -53,6 +265,11 @@ impl Canvas {
         }
     }
 
+    /// Iterate over the canvas one scanline at a time, from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[Color]> {
+        self.pixels.chunks(self.width.max(1))
+    }
+
     pub fn to_ppm(&self) -> String {
         let header = format!("P3\n{} {}\n255\n", self.width, self.height);
         let mut body = String::new();
@@ -109,11 +326,64 @@ impl Canvas {
 
         header + &body
     }
+
+    /// Encode the canvas as a Radiance `.hdr` image. Unlike `to_ppm` the colors
+    /// are not clamped, so highlights brighter than 1.0 are kept.
+    pub fn to_hdr_bytes(&self) -> Vec<u8> {
+        let header = format!(
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height, self.width
+        );
+        let mut bytes = header.into_bytes();
+
+        for color in &self.pixels {
+            bytes.extend_from_slice(&color_to_rgbe(color));
+        }
+
+        bytes
+    }
+}
+
+/// Encode a color as shared-exponent RGBE.
+fn color_to_rgbe(color: &Color) -> [u8; 4] {
+    let red = color.red().max(0.);
+    let green = color.green().max(0.);
+    let blue = color.blue().max(0.);
+    let value = red.max(green).max(blue);
+
+    if value < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    // value = mantissa * 2^exponent with mantissa in [0.5, 1)
+    let exponent = value.log2().floor() as i32 + 1;
+    let scale = 256. / 2f64.powi(exponent);
+
+    [
+        (red * scale).min(255.) as u8,
+        (green * scale).min(255.) as u8,
+        (blue * scale).min(255.) as u8,
+        (exponent + 128) as u8,
+    ]
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Canvas, Color};
+    use super::{color_to_rgbe, Canvas, Color, OutOfBounds};
+
+    fn rgbe_to_color(rgbe: [u8; 4]) -> Color {
+        if rgbe[3] == 0 {
+            return Color::new_black();
+        }
+
+        let scale = 2f64.powi(rgbe[3] as i32 - 128 - 8);
+
+        Color::new(
+            (rgbe[0] as f64 + 0.5) * scale,
+            (rgbe[1] as f64 + 0.5) * scale,
+            (rgbe[2] as f64 + 0.5) * scale,
+        )
+    }
 
     #[test]
     fn creating_a_canvas() {
@@ -126,6 +396,20 @@ mod tests {
         canvas.for_each(|x, y| assert_eq!(canvas.get(x, y), &black));
     }
 
+    #[test]
+    fn auto_exposure_moves_uniform_canvases_toward_middle_gray() {
+        let mut dim = Canvas::new_with_color(4, 4, Color::new(0.02, 0.02, 0.02));
+        let mut bright = Canvas::new_with_color(4, 4, Color::new(5., 5., 5.));
+
+        dim.auto_exposure();
+        bright.auto_exposure();
+
+        assert!(dim.get(0, 0).red() > 0.02);
+        assert!(bright.get(0, 0).red() < 5.);
+        assert!((dim.get(0, 0).red() - 0.18).abs() < 0.01);
+        assert!((bright.get(3, 3).red() - 0.18).abs() < 0.01);
+    }
+
     #[test]
     fn writing_pixels_to_a_canvas() {
         let mut c = Canvas::new(10, 20);
@@ -136,6 +420,28 @@ mod tests {
         assert_eq!(c.get(2, 3), &red)
     }
 
+    #[test]
+    fn collecting_rows_reconstructs_all_pixels_in_order() {
+        let mut c = Canvas::new(3, 2);
+
+        for y in 0..2 {
+            for x in 0..3 {
+                c.set(x, y, &Color::new(x as f64, y as f64, 0.));
+            }
+        }
+
+        let rows: Vec<&[Color]> = c.rows().collect();
+
+        assert_eq!(rows.len(), 2);
+        for (y, row) in rows.iter().enumerate() {
+            assert_eq!(row.len(), 3);
+            for (x, color) in row.iter().enumerate() {
+                assert_eq!(color, c.get(x, y));
+            }
+        }
+        assert_eq!(rows.concat(), c.pixels);
+    }
+
     #[test]
     fn constructing_the_ppm_header() {
         let c = Canvas::new(5, 3);
@@ -201,4 +507,86 @@ mod tests {
 
         assert!(ppm.ends_with('\n'));
     }
+
+    #[test]
+    fn try_set_past_the_edge_returns_an_error() {
+        let mut c = Canvas::new(3, 2);
+        let red = Color::new(1., 0., 0.);
+
+        assert_eq!(c.try_set(3, 0, &red), Err(OutOfBounds { x: 3, y: 0 }));
+        assert_eq!(c.try_set(0, 2, &red), Err(OutOfBounds { x: 0, y: 2 }));
+        assert_eq!(c.get(0, 1), &Color::new_black());
+
+        assert_eq!(c.try_set(2, 1, &red), Ok(()));
+        assert_eq!(c.try_get(2, 1), Ok(&red));
+        assert!(c.try_get(2, 2).is_err());
+    }
+
+    #[test]
+    fn hdr_output_keeps_values_above_one() {
+        let color = Color::new(3.5, 0.25, 1.);
+        let decoded = rgbe_to_color(color_to_rgbe(&color));
+
+        assert!((decoded.red() - 3.5).abs() < 0.02);
+        assert!((decoded.green() - 0.25).abs() < 0.02);
+        assert!((decoded.blue() - 1.).abs() < 0.02);
+    }
+
+    #[test]
+    fn hdr_output_starts_with_a_radiance_header() {
+        let mut c = Canvas::new(2, 1);
+        c.set(1, 0, &Color::new(3.5, 0., 0.));
+
+        let bytes = c.to_hdr_bytes();
+        let header = "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n";
+
+        assert!(bytes.starts_with(header.as_bytes()));
+        assert_eq!(bytes.len(), header.len() + 2 * 4);
+        assert_eq!(&bytes[header.len()..header.len() + 4], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn comparing_canvases_by_difference_and_psnr() {
+        let a = Canvas::new_with_color(4, 4, Color::new(0.5, 0.5, 0.5));
+        let mut b = Canvas::new_with_color(4, 4, Color::new(0.5, 0.5, 0.5));
+
+        assert_eq!(a.psnr(&b), f64::INFINITY);
+
+        b.set(1, 2, &Color::new(0.5, 0.25, 1.));
+        let difference = a.difference(&b);
+
+        assert_eq!(difference.get(1, 2), &Color::new(0., 0.25, 0.5));
+        assert_eq!(difference.get(0, 0), &Color::new_black());
+        assert!(a.psnr(&b).is_finite());
+        assert!(a.psnr(&b) > 0.);
+    }
+
+    #[test]
+    fn flipping_and_rotating_canvases() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.set(0, 0, &Color::new(1., 0., 0.));
+        canvas.set(2, 1, &Color::new(0., 1., 0.));
+
+        assert_eq!(canvas.flip_vertical().flip_vertical(), canvas);
+        assert_eq!(canvas.flip_vertical().get(0, 1), &Color::new(1., 0., 0.));
+        assert_eq!(canvas.flip_horizontal().get(2, 0), &Color::new(1., 0., 0.));
+
+        let clockwise = canvas.rotate_90(true);
+        assert_eq!((clockwise.width, clockwise.height), (2, 3));
+        assert_eq!(clockwise.get(1, 0), &Color::new(1., 0., 0.));
+        assert_eq!(clockwise.get(0, 2), &Color::new(0., 1., 0.));
+        assert_eq!(clockwise.rotate_90(false), canvas);
+    }
+
+    #[test]
+    fn focus_peaking_highlights_pixels_at_the_focal_distance() {
+        let canvas = Canvas::new_with_color(3, 1, Color::new(0.2, 0.4, 0.6));
+        let depth = [5., 5.05, f64::INFINITY];
+
+        let peaked = canvas.focus_peaking(&depth, 5., 0.1);
+
+        assert_eq!(peaked.get(0, 0), &Color::new(0.6, 0.2, 0.3));
+        assert_eq!(peaked.get(1, 0), &Color::new(0.6, 0.2, 0.3));
+        assert_eq!(peaked.get(2, 0), canvas.get(2, 0));
+    }
 }
@@ -1,4 +1,4 @@
-use std::ops::{Add, Mul, Sub};
+use core::ops::{Add, Mul, Sub};
 
 use crate::utils::fuzzy_equal::fuzzy_equal;
 
@@ -47,6 +47,47 @@ impl Color {
         self.blue
     }
 
+    /// Check whether every channel is (nearly) zero.
+    pub fn is_black(&self) -> bool {
+        fuzzy_equal(self.red, 0.) && fuzzy_equal(self.green, 0.) && fuzzy_equal(self.blue, 0.)
+    }
+
+    /// The perceived brightness of the color (Rec. 709 weights).
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+    }
+
+    /// Grade the color. Brightness scales every channel, saturation moves the
+    /// color away from (> 1) or toward (< 1) its luminance gray, and contrast
+    /// pushes channels away from or toward 0.5. `adjust(1., 1., 1.)` is a no-op.
+    pub fn adjust(&self, brightness: f64, saturation: f64, contrast: f64) -> Color {
+        let bright = self.clone() * brightness;
+        let gray = bright.luminance();
+        let channel = |c: f64| ((gray + (c - gray) * saturation) - 0.5) * contrast + 0.5;
+
+        Color::new(
+            channel(bright.red),
+            channel(bright.green),
+            channel(bright.blue),
+        )
+    }
+
+    /// Bring the color into the displayable range without shifting its hue:
+    /// if any channel exceeds 1.0 all channels are scaled down by the same
+    /// factor, negative channels are clamped to 0.
+    pub fn clamp_preserve_hue(&self) -> Color {
+        let red = self.red.max(0.);
+        let green = self.green.max(0.);
+        let blue = self.blue.max(0.);
+        let max = red.max(green).max(blue);
+
+        if max > 1. {
+            Color::new(red / max, green / max, blue / max)
+        } else {
+            Color::new(red, green, blue)
+        }
+    }
+
     pub fn clamp(x: f64) -> f64 {
         match x {
             x if x > 1.0 => 1.0,
@@ -112,6 +153,26 @@ impl Mul for Color {
     }
 }
 
+impl Mul<Color> for f64 {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Color {
+        rhs * self
+    }
+}
+
+impl Mul<&Color> for &Color {
+    type Output = Color;
+
+    fn mul(self, other: &Color) -> Color {
+        Color {
+            red: self.red * other.red,
+            green: self.green * other.green,
+            blue: self.blue * other.blue,
+        }
+    }
+}
+
 fn rgb_value_to_color(value: u8) -> f64 {
     (value as f64 * 100.) / (255. * 100.)
 }
@@ -139,6 +200,28 @@ mod tests {
         assert_eq!(c.blue, 1.7);
     }
 
+    #[test]
+    fn multiplying_by_a_scalar_works_from_either_side() {
+        let red = Color::new(1., 0., 0.);
+
+        assert_eq!(0.5 * red.clone(), red * 0.5);
+    }
+
+    #[test]
+    fn multiplying_color_references() {
+        let a = Color::new(1., 0.2, 0.4);
+        let b = Color::new(0.9, 1., 0.1);
+
+        assert_eq!(&a * &b, a.clone() * b.clone());
+    }
+
+    #[test]
+    fn only_black_is_black() {
+        assert!(Color::new_black().is_black());
+        assert!(!Color::new(0., 0.001, 0.).is_black());
+        assert!(!Color::new_white().is_black());
+    }
+
     #[test]
     fn adding_colors() {
         let c1 = Color::new(0.9, 0.6, 0.75);
@@ -176,4 +259,32 @@ mod tests {
 
         assert_eq!(c, Color::new(0.0, 0.0, 0.0));
     }
+
+    #[test]
+    fn zero_saturation_yields_the_luminance_gray() {
+        let c = Color::new(0.9, 0.4, 0.1);
+        let gray = c.luminance();
+
+        assert_eq!(c.adjust(1., 0., 1.), Color::new(gray, gray, gray));
+    }
+
+    #[test]
+    fn brightness_scales_each_channel() {
+        let c = Color::new(0.2, 0.3, 0.4);
+
+        assert_eq!(c.adjust(2., 1., 1.), Color::new(0.4, 0.6, 0.8));
+        assert_eq!(c.adjust(1., 1., 1.), c);
+    }
+
+    #[test]
+    fn clamping_while_preserving_hue_keeps_the_channel_ratio() {
+        assert_eq!(
+            Color::new(2., 1., 1.).clamp_preserve_hue(),
+            Color::new(1., 0.5, 0.5)
+        );
+        assert_eq!(
+            Color::new(0.5, -0.2, 0.3).clamp_preserve_hue(),
+            Color::new(0.5, 0., 0.3)
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use crate::math;
+
 use super::Color;
 
 #[derive(Debug, Clone)]
@@ -29,7 +31,7 @@ impl RGB {
 }
 
 fn color_value_to_rgb(value: f64) -> u8 {
-    (Color::clamp(value) * 255.0).round() as u8
+    math::round(Color::clamp(value) * 255.0) as u8
 }
 
 impl From<&Color> for RGB {
@@ -1,6 +1,8 @@
-use std::{borrow::Borrow, ops::Index, rc::Rc};
+use std::{borrow::Borrow, collections::HashMap, ops::Index, rc::Rc};
 
-use crate::{constants::EPSILON, ray::Ray, shapes::Shape, tuple::Tuple};
+use uuid::Uuid;
+
+use crate::{constants::EPSILON, ray::Ray, shapes::Shape, tuple::Tuple, utils::ordering::cmp_f64};
 
 pub struct ComputedIntersection {
     pub t: f64,
@@ -45,26 +47,41 @@ impl ComputedIntersection {
         }
     }
 
-    pub fn schlick(&self) -> f64 {
-        let mut cos = Tuple::dot(&self.eyev, &self.normalv);
+    /// The texture coordinates of the hit, if the object has a UV mapping.
+    pub fn uv(&self) -> Option<(f64, f64)> {
+        let local_point = self.object.get_transform().inverse() * self.point;
 
-        if self.n1 > self.n2 {
-            let n = self.n1 / self.n2;
-            let sin2_t = n.powf(2.) * (1. - cos.powf(2.));
+        self.object.local_uv_at(local_point)
+    }
 
-            if sin2_t > 1.0 {
-                return 1.0;
-            }
+    pub fn schlick(&self) -> f64 {
+        schlick(Tuple::dot(&self.eyev, &self.normalv), self.n1, self.n2)
+    }
+}
+
+/// Schlick's approximation of the Fresnel reflectance for light arriving at
+/// an angle with cosine `cos` (between the eye and the normal), passing from
+/// a medium with refractive index `n1` into one with `n2`. Total internal
+/// reflection gives 1.
+pub fn schlick(cos: f64, n1: f64, n2: f64) -> f64 {
+    let mut cos = cos;
 
-            let cos_t = (1.0 - sin2_t).sqrt();
+    if n1 > n2 {
+        let n = n1 / n2;
+        let sin2_t = n.powf(2.) * (1. - cos.powf(2.));
 
-            cos = cos_t;
+        if sin2_t > 1.0 {
+            return 1.0;
         }
 
-        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powf(2.);
+        let cos_t = (1.0 - sin2_t).sqrt();
 
-        r0 + (1. - r0) * (1. - cos).powf(5.)
+        cos = cos_t;
     }
+
+    let r0 = ((n1 - n2) / (n1 + n2)).powf(2.);
+
+    r0 + (1. - r0) * (1. - cos).powf(5.)
 }
 
 pub struct Intersection {
@@ -77,59 +94,61 @@ impl Intersection {
         Self { t, object }
     }
 
-    pub fn prepare_computations(&self, ray: &Ray, xs: &Intersections) -> ComputedIntersection {
+    /// Compute only the hit point, the normal facing the eye and whether the
+    /// hit is inside the object. Cheaper than `prepare_computations` when the
+    /// offset points and refractive indices aren't needed.
+    pub fn prepare_basic(&self, ray: &Ray) -> (Tuple, Tuple, bool) {
         let point = ray.position(self.t);
-        let mut normalv = self.object.normal_at(point);
-        let eyev = -ray.direction;
-        let inside;
-        let mut n1 = f64::NAN;
-        let mut n2 = f64::NAN;
+        let normalv = self.object.normal_at(point);
 
-        if Tuple::dot(&normalv, &eyev) < 0. {
-            inside = true;
-            normalv = -normalv;
+        if Tuple::dot(&normalv, &-ray.direction) < 0. {
+            (point, -normalv, true)
         } else {
-            inside = false;
+            (point, normalv, false)
         }
+    }
+
+    pub fn prepare_computations(&self, ray: &Ray, xs: &Intersections) -> ComputedIntersection {
+        let (point, normalv, inside) = self.prepare_basic(ray);
+        let eyev = -ray.direction;
+        let mut n1 = f64::NAN;
+        let mut n2 = f64::NAN;
 
-        let over_point = point + normalv * EPSILON;
-        let under_point = point - normalv * EPSILON;
+        let offset = self.object.shadow_offset();
+        let over_point = point + normalv * offset;
+        let under_point = point - normalv * offset;
         let reflectv = ray.direction.reflect(normalv);
 
-        let mut containers: Vec<Rc<dyn Shape>> = vec![];
+        // the objects the ray is inside, innermost last; exits leave a hole
+        // at the object's position so no entry has to be searched or moved
+        let mut containers: Vec<Option<&Rc<dyn Shape>>> = vec![];
+        let mut positions: HashMap<Uuid, usize> = HashMap::new();
+        let refractive_index = |containers: &[Option<&Rc<dyn Shape>>]| match containers.last() {
+            Some(Some(object)) => object.get_material().get_refractive_index(),
+            _ => 1.,
+        };
 
         for i in xs.data().iter() {
             if i == self {
-                if containers.is_empty() {
-                    n1 = 1.;
-                } else {
-                    n1 = containers
-                        .last()
-                        .unwrap()
-                        .get_material()
-                        .get_refractive_index();
-                }
+                n1 = refractive_index(&containers);
             }
 
-            if containers.contains(&i.object) {
-                containers = containers
-                    .into_iter()
-                    .filter(|item| item != &i.object)
-                    .collect();
-            } else {
-                containers.push(i.object.clone())
+            match positions.remove(&i.object.id()) {
+                Some(position) => {
+                    containers[position] = None;
+
+                    while let Some(None) = containers.last() {
+                        containers.pop();
+                    }
+                }
+                None => {
+                    positions.insert(i.object.id(), containers.len());
+                    containers.push(Some(&i.object));
+                }
             }
 
             if i == self {
-                if containers.is_empty() {
-                    n2 = 1.;
-                } else {
-                    n2 = containers
-                        .last()
-                        .unwrap()
-                        .get_material()
-                        .get_refractive_index();
-                }
+                n2 = refractive_index(&containers);
 
                 break;
             }
@@ -165,7 +184,7 @@ pub struct Intersections {
 
 impl Intersections {
     pub fn new(mut intersections: Vec<Intersection>) -> Self {
-        intersections.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
+        intersections.sort_by(|a, b| cmp_f64(&a.t, &b.t));
 
         Self {
             data: intersections,
@@ -181,6 +200,47 @@ impl Intersections {
         self.data.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The intersection with the smallest `t`, including negative ones.
+    pub fn first(&self) -> Option<&Intersection> {
+        self.data.first()
+    }
+
+    /// The intersection with the largest `t`.
+    pub fn last(&self) -> Option<&Intersection> {
+        self.data.last()
+    }
+
+    /// Combine with another list, keeping the result sorted by `t`.
+    pub fn merge(mut self, other: Intersections) -> Self {
+        self.extend(other.data);
+
+        self
+    }
+
+    /// Add intersections in any order, re-sorting once afterwards.
+    pub fn extend(&mut self, intersections: Vec<Intersection>) {
+        self.data.extend(intersections);
+        // The sort is stable and finds the already sorted runs, so merging
+        // sorted lists stays cheap
+        self.data.sort_by(|a, b| cmp_f64(&a.t, &b.t));
+    }
+
+    /// Merge intersections with different objects that lie within `EPSILON`
+    /// of each other, e.g. on coplanar faces, so they don't fight over which
+    /// one is shaded. The nearest one is kept, and for equal `t` the one
+    /// listed first when the intersections were created.
+    pub fn dedup_coincident(mut self) -> Self {
+        self.data.dedup_by(|current, kept| {
+            (current.t - kept.t).abs() < EPSILON && current.object.id() != kept.object.id()
+        });
+
+        self
+    }
+
     pub fn hit(&self) -> Option<&Intersection> {
         for intersection in self.data.iter() {
             if intersection.t > 0.0 {
@@ -209,7 +269,7 @@ impl Index<usize> for Intersections {
 mod tests {
     use crate::{
         constants::EPSILON,
-        intersections::Intersections,
+        intersections::{schlick, Intersections},
         material::Material,
         matrix::Matrix,
         ray::Ray,
@@ -412,6 +472,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn coincident_hits_on_different_objects_are_merged() {
+        let a = Plane::default();
+        let b = Plane::default();
+        let s = Sphere::default();
+
+        let xs = Intersections::new(vec![
+            b.intersection(2.),
+            s.intersection(-1.),
+            a.intersection(2.),
+            s.intersection(1.),
+            s.intersection(1.),
+        ])
+        .dedup_coincident();
+
+        assert_eq!(xs.len(), 4);
+        assert_eq!(xs.hit().unwrap().object.id(), s.id());
+        assert_eq!(xs[3].object.id(), b.id());
+    }
+
+    #[test]
+    fn merging_sorted_lists_keeps_them_sorted() {
+        let a = Sphere::default();
+        let b = Sphere::default();
+
+        let xs = Intersections::new(vec![a.intersection(-1.), a.intersection(3.)]);
+        let ys = Intersections::new(vec![b.intersection(1.), b.intersection(5.)]);
+        let mut merged = xs.merge(ys);
+
+        let ts: Vec<f64> = merged.data().iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![-1., 1., 3., 5.]);
+
+        merged.extend(vec![a.intersection(2.)]);
+
+        assert_eq!(merged.len(), 5);
+        assert_eq!(merged[2].t, 2.);
+        assert_eq!(merged.hit().unwrap().object.id(), b.id());
+    }
+
+    #[test]
+    fn finding_n1_and_n2_through_nested_spheres() {
+        let spheres: Vec<Sphere> = (1..=10)
+            .map(|k| {
+                Sphere::new_glass()
+                    .set_transform(Matrix::identity().scaling_uniform(11. - k as f64))
+                    .set_material(Material::default().set_refractive_index(1. + k as f64 / 10.))
+            })
+            .collect();
+        let index = |k: usize| if k == 0 { 1. } else { 1. + k as f64 / 10. };
+
+        // entering the spheres from the outside in, then leaving in reverse
+        let r = Ray::new(Tuple::point(0., 0., -20.), Tuple::vector(0., 0., 1.));
+        let xs = Intersections::new(
+            spheres
+                .iter()
+                .enumerate()
+                .flat_map(|(k, s)| {
+                    let radius = 10. - k as f64;
+
+                    vec![s.intersection(20. - radius), s.intersection(20. + radius)]
+                })
+                .collect(),
+        );
+
+        for k in 0..10 {
+            let entering = xs[k].prepare_computations(&r, &xs);
+            let leaving = xs[19 - k].prepare_computations(&r, &xs);
+
+            assert_eq!((entering.n1, entering.n2), (index(k), index(k + 1)));
+            assert_eq!((leaving.n1, leaving.n2), (index(k + 1), index(k)));
+        }
+    }
+
     #[test]
     fn the_schlick_approximation_under_total_internal_reflection() {
         let shape = Sphere::new_glass();
@@ -453,4 +586,54 @@ mod tests {
 
         assert!(fuzzy_equal(reflectance, 0.48873));
     }
+
+    #[test]
+    fn the_schlick_approximation_as_a_free_function() {
+        assert_eq!(schlick(2.0_f64.sqrt() / 2., 1.5, 1.), 1.);
+        assert!(fuzzy_equal(schlick(1., 1., 1.5), 0.04));
+        // the hit at t = 1.8589 in the book's small angle case
+        assert!(fuzzy_equal(schlick(0.1411, 1., 1.5), 0.48873));
+    }
+
+    #[test]
+    fn first_last_and_is_empty_on_a_collection_of_intersections() {
+        let s = Sphere::default();
+        let xs = Intersections::new(vec![s.intersection(2.), s.intersection(-1.)]);
+
+        assert!(!xs.is_empty());
+        assert_eq!(xs.first().unwrap().t, -1.);
+        assert_eq!(xs.last().unwrap().t, 2.);
+        assert!(Intersections::default().is_empty());
+        assert!(Intersections::default().first().is_none());
+    }
+
+    #[test]
+    fn prepare_basic_agrees_with_prepare_computations() {
+        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        let shape = Sphere::default();
+        let i = shape.intersection(1.);
+        let xs = Intersections::new(vec![shape.intersection(-1.), shape.intersection(1.)]);
+
+        let (point, normalv, inside) = i.prepare_basic(&r);
+        let comps = i.prepare_computations(&r, &xs);
+
+        assert_eq!(point, comps.point);
+        assert_eq!(normalv, comps.normalv);
+        assert_eq!(inside, comps.inside);
+        assert!(inside);
+    }
+
+    #[test]
+    fn a_hit_reports_the_texture_coordinates_of_the_object() {
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let sphere = Sphere::default().set_transform(Matrix::identity().scaling(2., 2., 2.));
+        let i = sphere.intersection(3.);
+
+        let comps = i.prepare_computations(&r, &Intersections::new(vec![]));
+        let (u, v) = comps.uv().unwrap();
+
+        assert!(fuzzy_equal(u, 0.));
+        assert!(fuzzy_equal(v, 0.5));
+        assert_eq!(comps.object.id(), sphere.id());
+    }
 }
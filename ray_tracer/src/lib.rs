@@ -1,14 +1,30 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+pub mod bounds;
+#[cfg(feature = "std")]
 pub mod camera;
+#[cfg(feature = "std")]
 pub mod canvas;
 pub mod color;
 pub mod constants;
+#[cfg(feature = "std")]
 pub mod intersections;
+#[cfg(feature = "std")]
 pub mod light;
+#[cfg(feature = "std")]
 pub mod material;
+pub mod math;
 pub mod matrix;
+#[cfg(feature = "std")]
 pub mod patterns;
+#[cfg(feature = "std")]
 pub mod ray;
+#[cfg(feature = "std")]
+pub mod sampling;
+#[cfg(feature = "std")]
 pub mod shapes;
 pub mod tuple;
 pub mod utils;
+#[cfg(feature = "std")]
 pub mod world;
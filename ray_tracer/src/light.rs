@@ -1,18 +1,71 @@
 use crate::{color::Color, tuple::Tuple};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Light {
     pub position: Tuple,
-    pub intensity: Color,
+    pub color: Color,
+    pub intensity: f64,
+    pub specular_enabled: bool,
+    /// Treat the light as a sphere of this radius when casting shadows, for
+    /// soft edges. 0 keeps hard shadows.
+    pub radius: f64,
 }
 
 impl Light {
-    pub fn new(position: Tuple, intensity: Color) -> Self {
+    /// Create a light of the given color with intensity 1 and specular
+    /// highlights enabled.
+    pub fn new(position: Tuple, color: Color) -> Self {
         Self {
             position,
-            intensity,
+            color,
+            intensity: 1.,
+            specular_enabled: true,
+            radius: 0.,
         }
     }
+
+    pub fn set_position(mut self, position: Tuple) -> Self {
+        self.position = position;
+
+        self
+    }
+
+    pub fn set_color(mut self, color: Color) -> Self {
+        self.color = color;
+
+        self
+    }
+
+    pub fn set_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+
+        self
+    }
+
+    /// Disable specular highlights for fill lights that should only add
+    /// diffuse light.
+    pub fn set_specular_enabled(mut self, specular_enabled: bool) -> Self {
+        self.specular_enabled = specular_enabled;
+
+        self
+    }
+
+    pub fn set_radius(mut self, radius: f64) -> Self {
+        self.radius = radius;
+
+        self
+    }
+
+    /// The color the light actually emits, i.e. its color scaled by its intensity.
+    pub fn effective_color(&self) -> Color {
+        self.color.clone() * self.intensity
+    }
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self::new(Tuple::point(0., 0., 0.), Color::new_white())
+    }
 }
 
 #[cfg(test)]
@@ -21,11 +74,24 @@ mod tests {
 
     #[test]
     fn a_point_light_has_a_position_and_intensity() {
-        let intensity = Color::new(1., 1., 1.);
+        let color = Color::new(1., 1., 1.);
         let position = Tuple::point(0., 0., 0.);
-        let light = Light::new(position, intensity.clone());
+        let light = Light::new(position, color.clone());
 
         assert_eq!(light.position, position);
-        assert_eq!(light.intensity, intensity);
+        assert_eq!(light.color, color);
+        assert_eq!(light.intensity, 1.);
+        assert!(light.specular_enabled);
+        assert_eq!(light.radius, 0.);
+        assert_eq!(light.effective_color(), color);
+    }
+
+    #[test]
+    fn setting_the_intensity_scales_the_effective_color() {
+        let light = Light::default()
+            .set_color(Color::new(1., 0.8, 0.6))
+            .set_intensity(0.5);
+
+        assert_eq!(light.effective_color(), Color::new(0.5, 0.4, 0.3));
     }
 }
@@ -3,7 +3,8 @@ use std::rc::Rc;
 use crate::{
     color::Color,
     light::Light,
-    patterns::{Pattern, Patterns},
+    patterns::{image_texture::ImageTexture, Pattern, Patterns},
+    sampling::tangent_frame,
     shapes::Shape,
     tuple::Tuple,
 };
@@ -15,10 +16,40 @@ pub struct Material {
     diffuse: f64,
     specular: f64,
     shininess: f64,
+    specular_model: SpecularModel,
     reflective: f64,
+    reflective_color: Color,
     transparency: f64,
     refractive_index: f64,
     pattern: Option<Patterns>,
+    dielectric: bool,
+    casts_shadow: bool,
+    flip_normals: bool,
+    two_sided: bool,
+    reflection_material: Option<Box<Material>>,
+    masked: Option<Box<MaskedMaterial>>,
+    bump_map: Option<ImageTexture>,
+    bump_strength: f64,
+    dispersion: f64,
+}
+
+/// How `Material::lighting` computes specular highlights.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SpecularModel {
+    /// Compare the reflected light vector with the eye vector.
+    #[default]
+    Phong,
+    /// Compare the normal with the half vector between light and eye. Cheaper,
+    /// with broader highlights for the same shininess.
+    BlinnPhong,
+}
+
+/// Two materials blended by a mask pattern, see `Material::masked`.
+#[derive(Debug, Clone, PartialEq)]
+struct MaskedMaterial {
+    base: Material,
+    overlay: Material,
+    mask: Patterns,
 }
 
 impl Material {
@@ -39,13 +70,88 @@ impl Material {
             diffuse,
             specular,
             shininess,
+            specular_model: SpecularModel::default(),
             reflective,
+            reflective_color: Color::new_white(),
             transparency,
             refractive_index,
             pattern,
+            dielectric: false,
+            casts_shadow: true,
+            flip_normals: false,
+            two_sided: false,
+            reflection_material: None,
+            masked: None,
+            bump_map: None,
+            bump_strength: 1.,
+            dispersion: 0.,
         }
     }
 
+    /// Blend between two materials by a mask pattern: where the mask is black
+    /// the base material is used, where it is white the overlay, and in
+    /// between every color and scalar property is interpolated when lighting.
+    /// Reflection and refraction use the base material's settings.
+    pub fn masked(base: Material, overlay: Material, mask: Patterns) -> Self {
+        Self {
+            masked: Some(Box::new(MaskedMaterial {
+                base: base.clone(),
+                overlay,
+                mask,
+            })),
+            ..base
+        }
+    }
+
+    /// A clear glass: fully transparent with the refractive index of glass.
+    pub fn glass() -> Self {
+        Self::default()
+            .set_transparency(1.)
+            .set_refractive_index(1.5)
+    }
+
+    /// A polished metal: dim diffuse, sharp highlights and reflections tinted
+    /// by the metal's color.
+    pub fn metal(color: Color) -> Self {
+        Self::default()
+            .set_color(color.clone())
+            .set_diffuse(0.3)
+            .set_specular(0.9)
+            .set_shininess(300.)
+            .set_reflective(0.7)
+            .set_reflective_color(color)
+    }
+
+    /// A glossy plastic: mostly diffuse with a white highlight and a faint
+    /// reflection.
+    pub fn plastic(color: Color) -> Self {
+        Self::default()
+            .set_color(color)
+            .set_diffuse(0.8)
+            .set_specular(0.5)
+            .set_shininess(100.)
+            .set_reflective(0.05)
+    }
+
+    /// A perfect mirror that shows almost only what it reflects.
+    pub fn mirror() -> Self {
+        Self::default()
+            .set_color(Color::new_black())
+            .set_ambient(0.)
+            .set_diffuse(0.05)
+            .set_specular(1.)
+            .set_shininess(300.)
+            .set_reflective(1.)
+    }
+
+    /// A rough surface such as chalk or paper, without highlights.
+    pub fn matte(color: Color) -> Self {
+        Self::default()
+            .set_color(color)
+            .set_diffuse(0.9)
+            .set_specular(0.)
+    }
+
     pub fn get_color(self) -> Color {
         self.color
     }
@@ -55,26 +161,51 @@ impl Material {
         self
     }
 
+    pub fn get_ambient(&self) -> f64 {
+        self.ambient
+    }
+
     pub fn set_ambient(mut self, ambient: f64) -> Self {
         self.ambient = ambient;
         self
     }
 
+    pub fn get_diffuse(&self) -> f64 {
+        self.diffuse
+    }
+
     pub fn set_diffuse(mut self, diffuse: f64) -> Self {
         self.diffuse = diffuse;
         self
     }
 
+    pub fn get_specular(&self) -> f64 {
+        self.specular
+    }
+
     pub fn set_specular(mut self, specular: f64) -> Self {
         self.specular = specular;
         self
     }
 
+    pub fn get_shininess(&self) -> f64 {
+        self.shininess
+    }
+
     pub fn set_shininess(mut self, shininess: f64) -> Self {
         self.shininess = shininess;
         self
     }
 
+    pub fn get_specular_model(&self) -> SpecularModel {
+        self.specular_model
+    }
+
+    pub fn set_specular_model(mut self, specular_model: SpecularModel) -> Self {
+        self.specular_model = specular_model;
+        self
+    }
+
     pub fn get_reflective(&self) -> f64 {
         self.reflective
     }
@@ -84,6 +215,20 @@ impl Material {
         self
     }
 
+    pub fn get_reflective_color(&self) -> &Color {
+        &self.reflective_color
+    }
+
+    /// Tint reflections, e.g. warm for gold. White keeps them neutral.
+    pub fn set_reflective_color(mut self, reflective_color: Color) -> Self {
+        self.reflective_color = reflective_color;
+        self
+    }
+
+    pub fn get_pattern(&self) -> Option<&Patterns> {
+        self.pattern.as_ref()
+    }
+
     pub fn set_pattern(mut self, pattern: Patterns) -> Self {
         self.pattern = Some(pattern);
         self
@@ -107,6 +252,98 @@ impl Material {
         self
     }
 
+    pub fn get_dispersion(&self) -> f64 {
+        self.dispersion
+    }
+
+    /// Spread the refractive index per color channel: red refracts with
+    /// `refractive_index - dispersion`, blue with `refractive_index +
+    /// dispersion`. Nonzero values trace three refraction rays per hit.
+    pub fn set_dispersion(mut self, dispersion: f64) -> Self {
+        self.dispersion = dispersion;
+        self
+    }
+
+    pub fn get_dielectric(&self) -> bool {
+        self.dielectric
+    }
+
+    /// Mark the material as a dielectric (glass, water, ...). Transparent
+    /// dielectrics reflect according to the Fresnel term even when
+    /// `reflective` is zero.
+    pub fn set_dielectric(mut self, dielectric: bool) -> Self {
+        self.dielectric = dielectric;
+        self
+    }
+
+    pub fn casts_shadow(&self) -> bool {
+        self.casts_shadow
+    }
+
+    /// Set whether objects with this material block light for shadow rays.
+    pub fn set_casts_shadow(mut self, casts_shadow: bool) -> Self {
+        self.casts_shadow = casts_shadow;
+        self
+    }
+
+    pub fn get_flip_normals(&self) -> bool {
+        self.flip_normals
+    }
+
+    /// Turn objects with this material inside out, e.g. for a sky dome that is
+    /// only ever seen from within.
+    pub fn set_flip_normals(mut self, flip_normals: bool) -> Self {
+        self.flip_normals = flip_normals;
+        self
+    }
+
+    pub fn get_two_sided(&self) -> bool {
+        self.two_sided
+    }
+
+    /// Light a surface from whichever side the light is on, e.g. for a plane
+    /// seen from above and lit from below.
+    pub fn set_two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
+    }
+
+    /// Check whether the material blends two materials, see `masked`.
+    pub fn is_masked(&self) -> bool {
+        self.masked.is_some()
+    }
+
+    pub fn get_reflection_material(&self) -> Option<&Material> {
+        self.reflection_material.as_deref()
+    }
+
+    /// Use a cheaper material when the object is seen through reflections or
+    /// refractions, e.g. a flat color in place of a detailed pattern.
+    pub fn set_reflection_material(mut self, reflection_material: Material) -> Self {
+        self.reflection_material = Some(Box::new(reflection_material));
+        self
+    }
+
+    pub fn get_bump_map(&self) -> Option<&ImageTexture> {
+        self.bump_map.as_ref()
+    }
+
+    /// Perturb the shading normal by a grayscale height map, adding surface
+    /// detail without extra geometry. Brighter texels are higher.
+    pub fn set_bump_map(mut self, bump_map: ImageTexture) -> Self {
+        self.bump_map = Some(bump_map);
+        self
+    }
+
+    pub fn get_bump_strength(&self) -> f64 {
+        self.bump_strength
+    }
+
+    pub fn set_bump_strength(mut self, bump_strength: f64) -> Self {
+        self.bump_strength = bump_strength;
+        self
+    }
+
     pub fn lighting(
         &self,
         object: Rc<dyn Shape>,
@@ -116,19 +353,67 @@ impl Material {
         normalv: Tuple,
         in_shadow: bool,
     ) -> Color {
+        let light_intensity = if in_shadow { 0. } else { 1. };
+
+        self.lighting_with_intensity(object, light, point, eyev, normalv, light_intensity)
+    }
+
+    /// Like `lighting`, but with the fraction of the light that reaches the
+    /// point (0 fully shadowed, 1 fully lit) instead of a shadow flag.
+    pub fn lighting_with_intensity(
+        &self,
+        object: Rc<dyn Shape>,
+        light: &Light,
+        point: Tuple,
+        eyev: Tuple,
+        normalv: Tuple,
+        light_intensity: f64,
+    ) -> Color {
+        let (ambient, diffuse, specular) =
+            self.lighting_terms(object, light, point, eyev, normalv, light_intensity);
+
+        ambient + diffuse + specular
+    }
+
+    /// The ambient, diffuse and specular terms that `lighting_with_intensity`
+    /// adds up, with the diffuse and specular terms already scaled by the
+    /// light intensity.
+    pub fn lighting_terms(
+        &self,
+        object: Rc<dyn Shape>,
+        light: &Light,
+        point: Tuple,
+        eyev: Tuple,
+        normalv: Tuple,
+        light_intensity: f64,
+    ) -> (Color, Color, Color) {
+        if let Some(masked) = &self.masked {
+            return masked.blend_at(object.clone(), point).lighting_terms(
+                object,
+                light,
+                point,
+                eyev,
+                normalv,
+                light_intensity,
+            );
+        }
+
         let ambient: Color;
         let diffuse: Color;
         let specular: Color;
-        let color: Color;
-
-        if let Some(pattern) = self.pattern.clone() {
-            color = pattern.pattern_at_shape(object, point);
+        let lightv = (light.position - point).normalize();
+        // the normal already faces the eye; a two-sided surface turns it
+        // toward the light instead, so it's lit from either side
+        let normalv = if self.two_sided && Tuple::dot(&lightv, &normalv) < 0. {
+            -normalv
         } else {
-            color = self.color.clone();
-        }
+            normalv
+        };
+        let normalv = self.bumped_normal(object.clone(), point, normalv);
+        let color = self.color_at(object, point);
 
-        let effective_color = color * light.intensity.clone();
-        let lightv = (light.position - point).normalize();
+        let light_color = light.effective_color();
+        let effective_color = &color * &light_color;
 
         ambient = effective_color.clone() * self.ambient;
 
@@ -140,23 +425,117 @@ impl Material {
         } else {
             diffuse = effective_color * self.diffuse * light_dot_normal;
 
-            let reflectv = -lightv.reflect(normalv);
-            let reflect_dot_eye = Tuple::dot(&reflectv, &eyev);
+            let highlight = match self.specular_model {
+                SpecularModel::Phong => {
+                    let reflectv = -lightv.reflect(normalv);
+
+                    Tuple::dot(&reflectv, &eyev)
+                }
+                SpecularModel::BlinnPhong => {
+                    let halfv = (lightv + eyev).normalize();
 
-            if reflect_dot_eye <= 0. {
+                    Tuple::dot(&normalv, &halfv)
+                }
+            };
+
+            if highlight <= 0. || !light.specular_enabled {
                 specular = Color::new_black();
             } else {
-                let factor = reflect_dot_eye.powf(self.shininess);
+                let factor = highlight.powf(self.shininess);
 
-                specular = light.intensity.clone() * self.specular * factor;
+                specular = light_color * self.specular * factor;
             }
         }
 
-        if in_shadow {
-            return ambient;
+        if light_intensity <= 0. {
+            return (ambient, Color::new_black(), Color::new_black());
+        }
+
+        (
+            ambient,
+            diffuse * light_intensity,
+            specular * light_intensity,
+        )
+    }
+
+    /// The ambient light picked up from the world's environment, in addition
+    /// to the flat ambient term of `lighting`.
+    pub fn environment_ambient(
+        &self,
+        object: Rc<dyn Shape>,
+        point: Tuple,
+        environment: Color,
+    ) -> Color {
+        self.color_at(object, point) * environment * self.ambient
+    }
+
+    /// Tilt the normal against the slope of the bump map, measured along two
+    /// tangents of the surface.
+    fn bumped_normal(&self, object: Rc<dyn Shape>, point: Tuple, normalv: Tuple) -> Tuple {
+        const DELTA: f64 = 1e-3;
+
+        let bump_map = match &self.bump_map {
+            Some(bump_map) => bump_map,
+            None => return normalv,
+        };
+
+        let to_pattern = bump_map.get_transform().inverse() * object.get_transform().inverse();
+        let pattern_point = to_pattern * point;
+        let height = |offset: Tuple| {
+            bump_map
+                .smooth_pattern_at(pattern_point + to_pattern * offset)
+                .luminance()
+        };
+
+        let (tangent, bitangent) = tangent_frame(normalv);
+
+        let base = height(Tuple::vector(0., 0., 0.));
+        let du = (height(tangent * DELTA) - base) / DELTA;
+        let dv = (height(bitangent * DELTA) - base) / DELTA;
+
+        (normalv - (tangent * du + bitangent * dv) * self.bump_strength).normalize()
+    }
+
+    /// The material's color at the point, taking the pattern into account.
+    fn color_at(&self, object: Rc<dyn Shape>, point: Tuple) -> Color {
+        match &self.pattern {
+            Some(pattern) => pattern.pattern_at_shape(object, point),
+            None => self.color.clone(),
         }
+    }
+}
+
+impl MaskedMaterial {
+    /// Collapse the blend into a plain material for the given point. Scalar
+    /// and color properties are interpolated, everything else is the base's.
+    fn blend_at(&self, object: Rc<dyn Shape>, point: Tuple) -> Material {
+        let mask = self.mask.pattern_at_shape(object.clone(), point);
+        let t = ((mask.red() + mask.green() + mask.blue()) / 3.).clamp(0., 1.);
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+        let lerp_color = |a: &Color, b: &Color| a.clone() * (1. - t) + b.clone() * t;
 
-        return ambient + diffuse + specular;
+        let base_color = self.base.color_at(object.clone(), point);
+        let overlay_color = self.overlay.color_at(object, point);
+
+        Material {
+            // the blended color already includes both patterns
+            pattern: None,
+            color: lerp_color(&base_color, &overlay_color),
+            ambient: lerp(self.base.ambient, self.overlay.ambient),
+            diffuse: lerp(self.base.diffuse, self.overlay.diffuse),
+            specular: lerp(self.base.specular, self.overlay.specular),
+            shininess: lerp(self.base.shininess, self.overlay.shininess),
+            reflective: lerp(self.base.reflective, self.overlay.reflective),
+            reflective_color: lerp_color(
+                &self.base.reflective_color,
+                &self.overlay.reflective_color,
+            ),
+            transparency: lerp(self.base.transparency, self.overlay.transparency),
+            refractive_index: lerp(self.base.refractive_index, self.overlay.refractive_index),
+            bump_strength: lerp(self.base.bump_strength, self.overlay.bump_strength),
+            dispersion: lerp(self.base.dispersion, self.overlay.dispersion),
+            ..self.base.clone()
+        }
     }
 }
 
@@ -168,10 +547,21 @@ impl Default for Material {
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            specular_model: SpecularModel::default(),
             reflective: 0.,
+            reflective_color: Color::new_white(),
             transparency: 0.,
             refractive_index: 1.,
             pattern: None,
+            dielectric: false,
+            casts_shadow: true,
+            flip_normals: false,
+            two_sided: false,
+            reflection_material: None,
+            masked: None,
+            bump_map: None,
+            bump_strength: 1.,
+            dispersion: 0.,
         }
     }
 }
@@ -181,8 +571,16 @@ mod tests {
     use std::rc::Rc;
 
     use crate::{
-        color::Color, light::Light, material::Material, patterns::stripe::Stripe,
-        shapes::sphere::Sphere, tuple::Tuple,
+        canvas::Canvas,
+        color::Color,
+        light::Light,
+        material::{Material, SpecularModel},
+        patterns::{
+            image_texture::{ImageTexture, UvMapping},
+            stripe::Stripe,
+        },
+        shapes::{plane::Plane, sphere::Sphere},
+        tuple::Tuple,
     };
 
     #[test]
@@ -196,6 +594,29 @@ mod tests {
         assert_eq!(m.shininess, 200.);
     }
 
+    #[test]
+    fn the_glass_material() {
+        let m = Material::glass();
+
+        assert_eq!(m.transparency, 1.);
+        assert_eq!(m.refractive_index, 1.5);
+    }
+
+    #[test]
+    fn material_presets() {
+        let mirror = Material::mirror();
+
+        assert_eq!(mirror.reflective, 1.);
+        assert!(mirror.diffuse < 0.1);
+        assert_eq!(Material::matte(Color::new(1., 0., 0.)).specular, 0.);
+
+        let gold = Color::new(1., 0.8, 0.3);
+        let metal = Material::metal(gold.clone());
+
+        assert_eq!(metal.reflective_color, gold);
+        assert!(metal.reflective > Material::plastic(gold).reflective);
+    }
+
     #[test]
     fn reflectivity_for_the_default_material() {
         let m = Material::default();
@@ -209,6 +630,15 @@ mod tests {
 
         assert_eq!(m.transparency, 0.);
         assert_eq!(m.refractive_index, 1.);
+        assert!(!m.dielectric);
+    }
+
+    #[test]
+    fn the_default_material_casts_shadows() {
+        let m = Material::default();
+
+        assert!(m.casts_shadow());
+        assert!(!m.set_casts_shadow(false).casts_shadow());
     }
 
     #[test]
@@ -295,6 +725,52 @@ mod tests {
         assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
     }
 
+    #[test]
+    fn lighting_with_a_fill_light_skips_the_specular_highlight() {
+        let m = Material::default();
+        let position = Tuple::point(0., 0., 0.);
+
+        let eyev = Tuple::vector(0., -2.0_f64.sqrt() / 2., -2.0_f64.sqrt() / 2.);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light = Light::new(Tuple::point(0., 10., -10.), Color::new(1., 1., 1.))
+            .set_specular_enabled(false);
+
+        let result = m.lighting(
+            Rc::new(Sphere::default()),
+            &light,
+            position,
+            eyev,
+            normalv,
+            false,
+        );
+
+        assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
+    }
+
+    #[test]
+    fn blinn_phong_broadens_the_highlight_but_keeps_its_peak() {
+        let phong = Material::default().set_shininess(10.);
+        let blinn = phong.clone().set_specular_model(SpecularModel::BlinnPhong);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light = Light::new(Tuple::point(0., 10., -10.), Color::new_white());
+        let shade = |m: &Material, eyev: Tuple| {
+            m.lighting(
+                Rc::new(Sphere::default()),
+                &light,
+                Tuple::point(0., 0., 0.),
+                eyev,
+                normalv,
+                false,
+            )
+        };
+
+        let mirror = Tuple::vector(0., -2.0_f64.sqrt() / 2., -2.0_f64.sqrt() / 2.);
+        let head_on = Tuple::vector(0., 0., -1.);
+
+        assert_eq!(shade(&phong, mirror), shade(&blinn, mirror));
+        assert!(shade(&blinn, head_on).red() > shade(&phong, head_on).red());
+    }
+
     #[test]
     fn lighting_with_the_light_behind_the_surface() {
         let m = Material::default();
@@ -370,4 +846,117 @@ mod tests {
         assert_eq!(c1, Color::new_white());
         assert_eq!(c2, Color::new_black());
     }
+
+    #[test]
+    fn lighting_a_masked_material_blends_base_and_overlay() {
+        let flat = |color| {
+            Material::default()
+                .set_color(color)
+                .set_ambient(1.)
+                .set_diffuse(0.)
+                .set_specular(0.)
+        };
+        let m = Material::masked(
+            flat(Color::new(1., 0., 0.)),
+            flat(Color::new(0., 0., 1.)),
+            Stripe::new(Color::new_black(), Color::new_white()).into(),
+        );
+
+        let eyev = Tuple::vector(0., 0., -1.);
+        let normalv = Tuple::vector(0., 0., -1.);
+        let light = Light::new(Tuple::point(0., 0., -10.), Color::new_white());
+
+        let base = m.lighting(
+            Rc::new(Sphere::default()),
+            &light,
+            Tuple::point(0.5, 0., 0.),
+            eyev,
+            normalv,
+            false,
+        );
+        let overlay = m.lighting(
+            Rc::new(Sphere::default()),
+            &light,
+            Tuple::point(1.5, 0., 0.),
+            eyev,
+            normalv,
+            false,
+        );
+
+        assert_eq!(base, Color::new(1., 0., 0.));
+        assert_eq!(overlay, Color::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn a_masked_material_keeps_the_other_properties_of_its_base() {
+        let base = Material::default()
+            .set_two_sided(true)
+            .set_reflective_color(Color::new(1., 0.5, 0.));
+        let m = Material::masked(
+            base,
+            Material::default(),
+            Stripe::new(Color::new_black(), Color::new_white()).into(),
+        );
+
+        let blended = m
+            .masked
+            .as_ref()
+            .unwrap()
+            .blend_at(Rc::new(Sphere::default()), Tuple::point(0.5, 0., 0.));
+
+        assert!(blended.get_two_sided());
+        assert_eq!(blended.get_reflective_color(), &Color::new(1., 0.5, 0.));
+        assert!(blended.masked.is_none());
+    }
+
+    #[test]
+    fn a_bump_map_varies_the_diffuse_shading_of_a_flat_plane() {
+        let mut ramp = Canvas::new(9, 1);
+        for x in 0..9 {
+            let height = x as f64 / 8.;
+            ramp.set(x, 0, &Color::new(height, height, height));
+        }
+
+        let flat = Material::default().set_ambient(0.).set_specular(0.);
+        let bumped = flat
+            .clone()
+            .set_bump_map(ImageTexture::new(ramp, UvMapping::Planar));
+
+        let eyev = Tuple::vector(0., 1., 0.);
+        let normalv = Tuple::vector(0., 1., 0.);
+        let light = Light::new(Tuple::point(0.5, 10., 0.5), Color::new_white());
+        let shade = |m: &Material, x: f64| {
+            m.lighting(
+                Rc::new(Plane::default()),
+                &light,
+                Tuple::point(x, 0., 0.5),
+                eyev,
+                normalv,
+                false,
+            )
+        };
+
+        assert_eq!(shade(&flat, 0.25), shade(&flat, 0.75));
+        assert_ne!(shade(&bumped, 0.25), shade(&bumped, 0.75));
+        assert_ne!(shade(&bumped, 0.25), shade(&flat, 0.25));
+    }
+
+    #[test]
+    fn a_two_sided_plane_lit_from_behind_is_shaded() {
+        let object = Rc::new(Plane::default());
+        let light = Light::new(Tuple::point(0., -10., 0.), Color::new_white());
+        let point = Tuple::point(0., 0., 0.);
+        let eyev = Tuple::vector(0., 1., 0.);
+        // the normal faces the eye, away from the light
+        let normalv = Tuple::vector(0., 1., 0.);
+
+        let one_sided =
+            Material::default().lighting(object.clone(), &light, point, eyev, normalv, false);
+        let two_sided = Material::default()
+            .set_two_sided(true)
+            .lighting(object, &light, point, eyev, normalv, false);
+
+        assert_eq!(one_sided, Color::new(0.1, 0.1, 0.1));
+        assert_eq!(two_sided, Color::new(1., 1., 1.));
+    }
 }
@@ -0,0 +1,45 @@
+//! Float functions for the core math types. They use the standard library
+//! when it is available and fall back to `libm` without it.
+
+macro_rules! float_fns {
+    ($($name:ident($($arg:ident),+) => $libm:ident),+ $(,)?) => {
+        $(
+            #[cfg(feature = "std")]
+            #[inline]
+            pub fn $name($($arg: f64),+) -> f64 {
+                f64::$name($($arg),+)
+            }
+
+            #[cfg(not(feature = "std"))]
+            #[inline]
+            pub fn $name($($arg: f64),+) -> f64 {
+                libm::$libm($($arg),+)
+            }
+        )+
+    };
+}
+
+float_fns!(
+    sqrt(x) => sqrt,
+    sin(x) => sin,
+    cos(x) => cos,
+    tan(x) => tan,
+    acos(x) => acos,
+    powf(x, y) => pow,
+    round(x) => round,
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::tuple::Tuple;
+
+    use super::sqrt;
+
+    #[test]
+    fn magnitude_matches_libm() {
+        let v = Tuple::vector(1., 2., 3.);
+
+        assert_eq!(v.magnitude(), libm::sqrt(14.));
+        assert_eq!(sqrt(14.), libm::sqrt(14.));
+    }
+}
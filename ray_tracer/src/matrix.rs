@@ -1,6 +1,6 @@
-use std::ops::{Index, IndexMut, Mul};
+use core::ops::{Index, IndexMut, Mul};
 
-use crate::{tuple::Tuple, utils::fuzzy_equal::fuzzy_equal};
+use crate::{constants::EPSILON, math, tuple::Tuple, utils::fuzzy_equal::fuzzy_equal};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Matrix<const D: usize> {
@@ -93,8 +93,14 @@ impl Matrix<4> {
     }
 
     pub fn inverse(&self) -> Self {
+        self.try_inverse().expect("matrix is not invertible")
+    }
+
+    /// Like `inverse`, but returns `None` for a singular matrix (e.g. a scaling
+    /// with a zero component) instead of panicking.
+    pub fn try_inverse(&self) -> Option<Self> {
         if !self.is_invertible() {
-            panic!("matrix is not invertible");
+            return None;
         }
 
         let mut result = Self::new();
@@ -108,6 +114,33 @@ impl Matrix<4> {
             }
         }
 
+        Some(result)
+    }
+
+    /// The matrix itself if it is invertible. Otherwise axes it collapses,
+    /// e.g. through a zero scale, are flattened to `EPSILON` instead, so the
+    /// transform stays usable as a very thin shape.
+    pub fn to_invertible(self) -> Self {
+        if self.is_invertible() {
+            return self;
+        }
+
+        let mut result = self;
+
+        for col in 0..3 {
+            let collapsed = (0..3).all(|row| result[row][col].abs() < EPSILON);
+
+            if collapsed {
+                result[col][col] = EPSILON;
+            }
+        }
+
+        if !result.is_invertible() {
+            for n in 0..3 {
+                result[n][n] += EPSILON;
+            }
+        }
+
         result
     }
 
@@ -129,32 +162,47 @@ impl Matrix<4> {
         m * self
     }
 
+    /// Translate by the x, y and z components of a tuple.
+    pub fn translation_tuple(self, t: Tuple) -> Self {
+        self.translation(t.x, t.y, t.z)
+    }
+
+    /// Scale by the x, y and z components of a tuple.
+    pub fn scaling_tuple(self, t: Tuple) -> Self {
+        self.scaling(t.x, t.y, t.z)
+    }
+
+    /// Scale every axis by the same factor.
+    pub fn scaling_uniform(self, s: f64) -> Self {
+        self.scaling(s, s, s)
+    }
+
     pub fn rotation_x(self, radians: f64) -> Self {
         let mut m = Self::identity();
-        m[1][1] = radians.cos();
-        m[1][2] = -radians.sin();
-        m[2][1] = radians.sin();
-        m[2][2] = radians.cos();
+        m[1][1] = math::cos(radians);
+        m[1][2] = -math::sin(radians);
+        m[2][1] = math::sin(radians);
+        m[2][2] = math::cos(radians);
 
         m * self
     }
 
     pub fn rotation_y(self, radians: f64) -> Self {
         let mut m = Self::identity();
-        m[0][0] = radians.cos();
-        m[0][2] = radians.sin();
-        m[2][0] = -radians.sin();
-        m[2][2] = radians.cos();
+        m[0][0] = math::cos(radians);
+        m[0][2] = math::sin(radians);
+        m[2][0] = -math::sin(radians);
+        m[2][2] = math::cos(radians);
 
         m * self
     }
 
     pub fn rotation_z(self, radians: f64) -> Self {
         let mut m = Self::identity();
-        m[0][0] = radians.cos();
-        m[0][1] = -radians.sin();
-        m[1][0] = radians.sin();
-        m[1][1] = radians.cos();
+        m[0][0] = math::cos(radians);
+        m[0][1] = -math::sin(radians);
+        m[1][0] = math::sin(radians);
+        m[1][1] = math::cos(radians);
 
         m * self
     }
@@ -172,9 +220,19 @@ impl Matrix<4> {
     }
 
     pub fn view_transform(&self, from: Tuple, to: Tuple, up: Tuple) -> Self {
-        let forward = (to - from).normalize();
-        let upn = up.normalize();
+        self.try_view_transform(from, to, up)
+            .expect("degenerate view transform, see try_view_transform")
+    }
+
+    /// Like `view_transform`, but returns `None` when `from` equals `to`, `up`
+    /// is zero or `up` is parallel to the view direction.
+    pub fn try_view_transform(&self, from: Tuple, to: Tuple, up: Tuple) -> Option<Self> {
+        let forward = (to - from).try_normalize()?;
+        let upn = up.try_normalize()?;
         let left = Tuple::cross(&forward, &upn);
+
+        left.try_normalize()?;
+
         let true_up = Tuple::cross(&left, &forward);
 
         #[rustfmt::skip]
@@ -185,8 +243,154 @@ impl Matrix<4> {
             [        0.,         0.,         0., 1.],
         ]);
 
-        orientation * Matrix::identity().translation(-from.x, -from.y, -from.z)
+        Some(orientation * Matrix::identity().translation(-from.x, -from.y, -from.z))
+    }
+
+    /// Interpolate between two affine transforms for keyframing. Both matrices
+    /// are decomposed into translation, rotation and scale; translation and
+    /// scale are lerped and rotation is slerped. Shearing is not preserved.
+    pub fn interpolate(&self, other: &Self, t: f64) -> Self {
+        let (translation_a, rotation_a, scale_a) = self.decompose();
+        let (translation_b, rotation_b, scale_b) = other.decompose();
+
+        Self::compose(
+            translation_a.lerp(translation_b, t),
+            slerp(rotation_a, rotation_b, t),
+            scale_a.lerp(scale_b, t),
+        )
+    }
+
+    fn decompose(&self) -> (Tuple, [f64; 4], Tuple) {
+        let translation = Tuple::vector(self[0][3], self[1][3], self[2][3]);
+        let column = |col: usize| Tuple::vector(self[0][col], self[1][col], self[2][col]);
+
+        let mut scale = Tuple::vector(
+            column(0).magnitude(),
+            column(1).magnitude(),
+            column(2).magnitude(),
+        );
+
+        if self.submatrix(3, 3).determinant() < 0. {
+            scale.x = -scale.x;
+        }
+
+        let mut rotation = Matrix::<3>::identity();
+        let scales = [scale.x, scale.y, scale.z];
+
+        for row in 0..3 {
+            for col in 0..3 {
+                rotation[row][col] = self[row][col] / scales[col];
+            }
+        }
+
+        (translation, quaternion_from_rotation(&rotation), scale)
+    }
+
+    fn compose(translation: Tuple, rotation: [f64; 4], scale: Tuple) -> Self {
+        let [w, x, y, z] = rotation;
+        let scales = [scale.x, scale.y, scale.z];
+
+        #[rustfmt::skip]
+        let rotation = [
+            [1. - 2. * (y * y + z * z),      2. * (x * y - w * z),      2. * (x * z + w * y)],
+            [     2. * (x * y + w * z), 1. - 2. * (x * x + z * z),      2. * (y * z - w * x)],
+            [     2. * (x * z - w * y),      2. * (y * z + w * x), 1. - 2. * (x * x + y * y)],
+        ];
+
+        let mut result = Self::identity();
+
+        for row in 0..3 {
+            for col in 0..3 {
+                result[row][col] = rotation[row][col] * scales[col];
+            }
+        }
+
+        result[0][3] = translation.x;
+        result[1][3] = translation.y;
+        result[2][3] = translation.z;
+
+        result
+    }
+}
+
+/// Convert a pure rotation matrix into a unit quaternion `[w, x, y, z]`.
+fn quaternion_from_rotation(m: &Matrix<3>) -> [f64; 4] {
+    let trace = m[0][0] + m[1][1] + m[2][2];
+
+    let q = if trace > 0. {
+        let s = math::sqrt(trace + 1.) * 2.;
+        [
+            0.25 * s,
+            (m[2][1] - m[1][2]) / s,
+            (m[0][2] - m[2][0]) / s,
+            (m[1][0] - m[0][1]) / s,
+        ]
+    } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+        let s = math::sqrt(1. + m[0][0] - m[1][1] - m[2][2]) * 2.;
+        [
+            (m[2][1] - m[1][2]) / s,
+            0.25 * s,
+            (m[0][1] + m[1][0]) / s,
+            (m[0][2] + m[2][0]) / s,
+        ]
+    } else if m[1][1] > m[2][2] {
+        let s = math::sqrt(1. + m[1][1] - m[0][0] - m[2][2]) * 2.;
+        [
+            (m[0][2] - m[2][0]) / s,
+            (m[0][1] + m[1][0]) / s,
+            0.25 * s,
+            (m[1][2] + m[2][1]) / s,
+        ]
+    } else {
+        let s = math::sqrt(1. + m[2][2] - m[0][0] - m[1][1]) * 2.;
+        [
+            (m[1][0] - m[0][1]) / s,
+            (m[0][2] + m[2][0]) / s,
+            (m[1][2] + m[2][1]) / s,
+            0.25 * s,
+        ]
+    };
+
+    normalize_quaternion(q)
+}
+
+fn normalize_quaternion(q: [f64; 4]) -> [f64; 4] {
+    let length = math::sqrt(q.iter().map(|c| c * c).sum::<f64>());
+
+    [q[0] / length, q[1] / length, q[2] / length, q[3] / length]
+}
+
+/// Spherical linear interpolation between two unit quaternions.
+fn slerp(a: [f64; 4], b: [f64; 4], t: f64) -> [f64; 4] {
+    let mut b = b;
+    let mut dot = a[0] * b[0] + a[1] * b[1] + a[2] * b[2] + a[3] * b[3];
+
+    // take the shortest path around the sphere
+    if dot < 0. {
+        b = [-b[0], -b[1], -b[2], -b[3]];
+        dot = -dot;
     }
+
+    // nearly identical rotations, fall back to a normalized lerp
+    if dot > 0.9995 {
+        return normalize_quaternion([
+            a[0] + (b[0] - a[0]) * t,
+            a[1] + (b[1] - a[1]) * t,
+            a[2] + (b[2] - a[2]) * t,
+            a[3] + (b[3] - a[3]) * t,
+        ]);
+    }
+
+    let theta = math::acos(dot);
+    let wa = math::sin((1. - t) * theta) / math::sin(theta);
+    let wb = math::sin(t * theta) / math::sin(theta);
+
+    [
+        a[0] * wa + b[0] * wb,
+        a[1] * wa + b[1] * wb,
+        a[2] * wa + b[2] * wb,
+        a[3] * wa + b[3] * wb,
+    ]
 }
 
 impl Matrix<3> {
@@ -304,7 +508,7 @@ impl Mul<Tuple> for Matrix<4> {
 
 #[cfg(test)]
 mod tests {
-    use std::f64::consts::PI;
+    use core::f64::consts::PI;
 
     use crate::{matrix::Matrix, tuple::Tuple};
 
@@ -623,6 +827,31 @@ mod tests {
         assert!(!a.is_invertible());
     }
 
+    #[test]
+    fn a_degenerate_view_transform_is_rejected() {
+        let p = Tuple::point(1., 2., 3.);
+
+        assert!(Matrix::identity()
+            .try_view_transform(p, p, Tuple::vector(0., 1., 0.))
+            .is_none());
+        assert!(Matrix::identity()
+            .try_view_transform(p, Tuple::point(1., 5., 3.), Tuple::vector(0., 1., 0.))
+            .is_none());
+    }
+
+    #[test]
+    fn try_inverse_of_a_zero_scaling_is_none() {
+        let m = Matrix::identity().scaling(1., 0., 1.);
+
+        assert!(m.try_inverse().is_none());
+        assert!(m.to_invertible().is_invertible());
+        assert_eq!(Matrix::identity().to_invertible(), Matrix::identity());
+        assert_eq!(
+            Matrix::identity().scaling(2., 2., 2.).try_inverse(),
+            Some(Matrix::identity().scaling(0.5, 0.5, 0.5))
+        );
+    }
+
     #[test]
     fn calculating_the_inverse_of_a_matrix() {
         #[rustfmt::skip]
@@ -744,6 +973,22 @@ mod tests {
         assert_eq!(transform * p, Tuple::point(-8., 18., 32.));
     }
 
+    #[test]
+    fn translation_and_scaling_from_tuples_and_scalars() {
+        assert_eq!(
+            Matrix::identity().scaling_uniform(2.),
+            Matrix::identity().scaling(2., 2., 2.)
+        );
+        assert_eq!(
+            Matrix::identity().translation_tuple(Tuple::vector(1., 2., 3.)),
+            Matrix::identity().translation(1., 2., 3.)
+        );
+        assert_eq!(
+            Matrix::identity().scaling_tuple(Tuple::vector(1., 2., 3.)),
+            Matrix::identity().scaling(1., 2., 3.)
+        );
+    }
+
     #[test]
     fn a_scaling_matrix_applied_to_a_vector() {
         let transform = Matrix::identity().scaling(2., 3., 4.);
@@ -966,4 +1211,52 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    fn interpolating_two_translations_yields_the_midpoint() {
+        let a = Matrix::identity().translation(0., 2., -4.);
+        let b = Matrix::identity().translation(4., 0., 6.);
+
+        assert_eq!(
+            a.interpolate(&b, 0.5),
+            Matrix::identity().translation(2., 1., 1.)
+        );
+    }
+
+    #[test]
+    fn interpolating_transforms_matches_the_keyframes_at_the_ends() {
+        let a = Matrix::identity()
+            .scaling(1., 2., 3.)
+            .rotation_y(PI / 6.)
+            .translation(1., 0., 0.);
+        let b = Matrix::identity()
+            .scaling(2., 2., 2.)
+            .rotation_x(PI / 3.)
+            .translation(0., 5., 0.);
+
+        assert_eq!(a.interpolate(&b, 0.), a);
+        assert_eq!(a.interpolate(&b, 1.), b);
+    }
+
+    #[test]
+    fn interpolating_rotations_slerps_the_angle() {
+        let a = Matrix::identity();
+        let b = Matrix::identity().rotation_z(PI / 2.);
+
+        assert_eq!(
+            a.interpolate(&b, 0.5),
+            Matrix::identity().rotation_z(PI / 4.)
+        );
+    }
+
+    #[test]
+    fn interpolating_scales_lerps_each_axis() {
+        let a = Matrix::identity().scaling(1., 1., 1.);
+        let b = Matrix::identity().scaling(3., 5., 1.);
+
+        assert_eq!(
+            a.interpolate(&b, 0.5),
+            Matrix::identity().scaling(2., 3., 1.)
+        );
+    }
 }
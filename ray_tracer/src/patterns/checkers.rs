@@ -1,12 +1,13 @@
 use crate::{color::Color, matrix::Matrix, tuple::Tuple};
 
-use super::Pattern;
+use super::{odd_fraction, scale_point, Pattern};
 
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Checkers {
     a: Color,
     b: Color,
     transform: Matrix<4>,
+    scale: f64,
 }
 
 impl Checkers {
@@ -15,8 +16,37 @@ impl Checkers {
             a,
             b,
             transform: Matrix::identity(),
+            scale: 1.,
         }
     }
+
+    /// Get a reference to the checkers pattern's first color.
+    pub fn a(&self) -> &Color {
+        &self.a
+    }
+
+    /// Get a reference to the checkers pattern's second color.
+    pub fn b(&self) -> &Color {
+        &self.b
+    }
+
+    /// Get the checkers pattern's scale.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Multiply lookup points by `scale`, so 2 repeats the pattern twice as
+    /// often. Composes with the pattern's transform.
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+impl Default for Checkers {
+    fn default() -> Self {
+        Self::new(Color::default(), Color::default())
+    }
 }
 
 impl Pattern for Checkers {
@@ -30,12 +60,30 @@ impl Pattern for Checkers {
     }
 
     fn pattern_at(&self, point: Tuple) -> Color {
+        let point = scale_point(point, self.scale);
+
         if (point.x.floor() + point.y.floor() + point.z.floor()) % 2.0 == 0.0 {
             self.a.clone()
         } else {
             self.b.clone()
         }
     }
+
+    fn pattern_at_footprint(&self, point: Tuple, radius: Option<f64>) -> Color {
+        match radius {
+            Some(r) if r > 0. => {
+                let point = scale_point(point, self.scale);
+                let r = r * self.scale;
+
+                // the parity of each axis averages independently over the box
+                let parity = |x: f64| 1. - 2. * odd_fraction(x - r, x + r);
+                let weight = (1. + parity(point.x) * parity(point.y) * parity(point.z)) / 2.;
+
+                self.a.clone() * weight + self.b.clone() * (1. - weight)
+            }
+            _ => self.pattern_at(point),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -97,4 +145,41 @@ mod tests {
             Color::new_black()
         );
     }
+
+    #[test]
+    fn a_footprint_across_a_checker_corner_averages_the_colors() {
+        let pattern = Checkers::new(Color::new_white(), Color::new_black());
+
+        let corner = pattern.pattern_at_footprint(Tuple::point(1., 0.5, 1.), Some(0.25));
+        let edge = pattern.pattern_at_footprint(Tuple::point(1., 0.5, 0.5), Some(0.25));
+        let inside = pattern.pattern_at_footprint(Tuple::point(0.5, 0.5, 0.5), Some(0.25));
+
+        assert_eq!(corner, Color::new(0.5, 0.5, 0.5));
+        assert_eq!(edge, Color::new(0.5, 0.5, 0.5));
+        assert_eq!(inside, Color::new_white());
+    }
+
+    #[test]
+    fn a_scale_of_two_halves_the_checker_cells() {
+        let default = Checkers::new(Color::new_white(), Color::new_black());
+        let scaled = default.clone().with_scale(2.);
+
+        // the first cell boundary moves from x = 1 to x = 0.5
+        assert_eq!(
+            default.pattern_at(Tuple::point(0.75, 0., 0.)),
+            Color::new_white()
+        );
+        assert_eq!(
+            scaled.pattern_at(Tuple::point(0.75, 0., 0.)),
+            Color::new_black()
+        );
+        assert_eq!(
+            scaled.pattern_at(Tuple::point(0.25, 0., 0.)),
+            Color::new_white()
+        );
+        assert_eq!(
+            scaled.pattern_at(Tuple::point(1.25, 0., 0.)),
+            Color::new_white()
+        );
+    }
 }
@@ -0,0 +1,97 @@
+use crate::{color::Color, matrix::Matrix, tuple::Tuple};
+
+use super::Pattern;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    line_color: Color,
+    bg_color: Color,
+    spacing: f64,
+    thickness: f64,
+    transform: Matrix<4>,
+}
+
+impl Grid {
+    pub fn new(line_color: Color, bg_color: Color, spacing: f64, thickness: f64) -> Self {
+        Self {
+            line_color,
+            bg_color,
+            spacing,
+            thickness,
+            transform: Matrix::identity(),
+        }
+    }
+
+    /// Check if the value lies within the grid's thickness of a multiple of spacing.
+    fn on_line(&self, value: f64) -> bool {
+        let offset = value.rem_euclid(self.spacing);
+
+        offset.min(self.spacing - offset) <= self.thickness
+    }
+}
+
+impl Pattern for Grid {
+    fn get_transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform;
+        self.clone()
+    }
+
+    fn pattern_at(&self, point: Tuple) -> Color {
+        if self.on_line(point.x) || self.on_line(point.z) {
+            self.line_color.clone()
+        } else {
+            self.bg_color.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{color::Color, patterns::grid::Grid, tuple::Tuple};
+
+    use super::Pattern;
+
+    #[test]
+    fn a_grid_returns_the_line_color_on_grid_lines() {
+        let pattern = Grid::new(Color::new_white(), Color::new_black(), 1., 0.05);
+
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0., 0., 0.5)),
+            Color::new_white()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.5, 0., 2.)),
+            Color::new_white()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(-1.03, 0., 0.5)),
+            Color::new_white()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.5, 0., 2.98)),
+            Color::new_white()
+        );
+    }
+
+    #[test]
+    fn a_grid_returns_the_background_color_between_lines() {
+        let pattern = Grid::new(Color::new_white(), Color::new_black(), 1., 0.05);
+
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.5, 0., 0.5)),
+            Color::new_black()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(-0.5, 3., 1.5)),
+            Color::new_black()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.9, 0., 0.1)),
+            Color::new_black()
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{canvas::Canvas, color::Color, matrix::Matrix, tuple::Tuple};
+
+use super::Pattern;
+
+/// How a point in pattern space is projected onto `(u, v)` texture
+/// coordinates in `[0, 1)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UvMapping {
+    /// Wrap around the unit sphere by longitude and latitude.
+    Spherical,
+    /// Tile the xz plane with unit squares.
+    Planar,
+    /// Project onto the faces of the unit cube, each showing the whole image.
+    Cube,
+}
+
+impl UvMapping {
+    pub fn uv_at(&self, point: Tuple) -> (f64, f64) {
+        match self {
+            UvMapping::Spherical => {
+                let theta = point.x.atan2(point.z);
+                let radius = (point - Tuple::point(0., 0., 0.)).magnitude();
+                let phi = (point.y / radius).acos();
+
+                (1. - (theta / (2. * PI) + 0.5), 1. - phi / PI)
+            }
+            UvMapping::Planar => (point.x.rem_euclid(1.), point.z.rem_euclid(1.)),
+            UvMapping::Cube => {
+                let face =
+                    |u: f64, v: f64| ((u + 1.).rem_euclid(2.) / 2., (v + 1.).rem_euclid(2.) / 2.);
+                let (x, y, z) = (point.x, point.y, point.z);
+                let max = x.abs().max(y.abs()).max(z.abs());
+
+                if max == x {
+                    face(-z, y)
+                } else if max == -x {
+                    face(z, y)
+                } else if max == y {
+                    face(x, -z)
+                } else if max == -y {
+                    face(x, z)
+                } else if max == z {
+                    face(x, y)
+                } else {
+                    face(-x, y)
+                }
+            }
+        }
+    }
+}
+
+/// A pattern that looks colors up in an image. Clones share the image, so
+/// one texture can go on many shapes without copying its pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageTexture {
+    canvas: Arc<Canvas>,
+    mapping: UvMapping,
+    transform: Matrix<4>,
+}
+
+impl ImageTexture {
+    /// Panics if the canvas has no pixels.
+    pub fn new(canvas: impl Into<Arc<Canvas>>, mapping: UvMapping) -> Self {
+        let canvas = canvas.into();
+
+        assert!(
+            canvas.width > 0 && canvas.height > 0,
+            "an image texture needs at least one pixel"
+        );
+
+        Self {
+            canvas,
+            mapping,
+            transform: Matrix::identity(),
+        }
+    }
+
+    /// Get a reference to the texture's image.
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    /// Get the texture's uv mapping.
+    pub fn mapping(&self) -> UvMapping {
+        self.mapping
+    }
+
+    /// The color of the pixel nearest to the `(u, v)` coordinates, with `v`
+    /// pointing up the image. Coordinates outside `[0, 1]` take the color at
+    /// the edge.
+    pub fn uv_pattern_at(&self, u: f64, v: f64) -> Color {
+        let (x, y) = self.image_position(u, v);
+        let (x, y) = (x.round() as usize, y.round() as usize);
+
+        self.canvas.get(x, y).clone()
+    }
+
+    /// Like `pattern_at`, but blends the four nearest pixels so the result
+    /// changes smoothly across the image.
+    pub fn smooth_pattern_at(&self, point: Tuple) -> Color {
+        let (u, v) = self.mapping.uv_at(point);
+        let (x, y) = self.image_position(u, v);
+
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = (
+            (x0 + 1).min(self.canvas.width - 1),
+            (y0 + 1).min(self.canvas.height - 1),
+        );
+        let (tx, ty) = (x - x0 as f64, y - y0 as f64);
+
+        let top =
+            self.canvas.get(x0, y0).clone() * (1. - tx) + self.canvas.get(x1, y0).clone() * tx;
+        let bottom =
+            self.canvas.get(x0, y1).clone() * (1. - tx) + self.canvas.get(x1, y1).clone() * tx;
+
+        top * (1. - ty) + bottom * ty
+    }
+
+    /// The `(u, v)` coordinates clamped to `[0, 1]` and scaled to pixel
+    /// positions, from `0` to the last column and row.
+    fn image_position(&self, u: f64, v: f64) -> (f64, f64) {
+        (
+            u.clamp(0., 1.) * (self.canvas.width - 1) as f64,
+            (1. - v.clamp(0., 1.)) * (self.canvas.height - 1) as f64,
+        )
+    }
+}
+
+impl Pattern for ImageTexture {
+    fn get_transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform;
+        self.clone()
+    }
+
+    fn pattern_at(&self, point: Tuple) -> Color {
+        let (u, v) = self.mapping.uv_at(point);
+
+        self.uv_pattern_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        canvas::Canvas,
+        color::Color,
+        patterns::{
+            image_texture::{ImageTexture, UvMapping},
+            Pattern,
+        },
+        tuple::Tuple,
+        utils::fuzzy_equal::fuzzy_equal,
+    };
+
+    #[test]
+    fn using_a_spherical_mapping_on_a_3d_point() {
+        let examples = vec![
+            (Tuple::point(0., 0., -1.), 0., 0.5),
+            (Tuple::point(1., 0., 0.), 0.25, 0.5),
+            (Tuple::point(0., 0., 1.), 0.5, 0.5),
+            (Tuple::point(-1., 0., 0.), 0.75, 0.5),
+            (Tuple::point(0., 1., 0.), 0.5, 1.),
+            (Tuple::point(0., -1., 0.), 0.5, 0.),
+        ];
+
+        for (point, u, v) in examples.into_iter() {
+            let uv = UvMapping::Spherical.uv_at(point);
+
+            assert!(fuzzy_equal(uv.0, u));
+            assert!(fuzzy_equal(uv.1, v));
+        }
+    }
+
+    #[test]
+    fn using_a_planar_mapping_on_a_3d_point() {
+        let uv = UvMapping::Planar.uv_at(Tuple::point(-0.25, 0.5, 1.75));
+
+        assert!(fuzzy_equal(uv.0, 0.75));
+        assert!(fuzzy_equal(uv.1, 0.75));
+    }
+
+    #[test]
+    fn using_a_cube_mapping_on_the_front_face() {
+        let uv = UvMapping::Cube.uv_at(Tuple::point(-0.5, 0.5, 1.));
+
+        assert!(fuzzy_equal(uv.0, 0.25));
+        assert!(fuzzy_equal(uv.1, 0.75));
+    }
+
+    #[test]
+    fn an_image_texture_samples_the_nearest_pixel() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.set(0, 0, &Color::new(1., 0., 0.));
+        canvas.set(1, 1, &Color::new(0., 0., 1.));
+        let texture = ImageTexture::new(canvas, UvMapping::Planar);
+
+        assert_eq!(
+            texture.pattern_at(Tuple::point(0., 0., 0.9)),
+            Color::new(1., 0., 0.)
+        );
+        assert_eq!(
+            texture.pattern_at(Tuple::point(0.9, 0., 0.)),
+            Color::new(0., 0., 1.)
+        );
+    }
+
+    #[test]
+    fn an_image_texture_clamps_coordinates_outside_the_image() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.set(0, 0, &Color::new(1., 0., 0.));
+        canvas.set(1, 1, &Color::new(0., 0., 1.));
+        let texture = ImageTexture::new(canvas, UvMapping::Planar);
+
+        assert_eq!(texture.uv_pattern_at(-0.5, 1.5), Color::new(1., 0., 0.));
+        assert_eq!(texture.uv_pattern_at(1.5, -0.5), Color::new(0., 0., 1.));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one pixel")]
+    fn an_image_texture_rejects_an_empty_canvas() {
+        ImageTexture::new(Canvas::new(0, 0), UvMapping::Planar);
+    }
+
+    #[test]
+    fn clones_of_an_image_texture_share_the_image() {
+        let texture = ImageTexture::new(Canvas::new(2, 2), UvMapping::Planar);
+        let clone = texture.clone();
+
+        assert!(std::ptr::eq(texture.canvas(), clone.canvas()));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{color::Color, matrix::Matrix, tuple::Tuple};
+
+use super::{noise::Perlin, scale_point, Pattern};
+
+const OCTAVES: usize = 6;
+
+/// Veins of `b` through `a`, from a sine along x displaced by turbulence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marble {
+    a: Color,
+    b: Color,
+    transform: Matrix<4>,
+    scale: f64,
+    turbulence: f64,
+    noise: Perlin,
+}
+
+impl Marble {
+    pub fn new(a: Color, b: Color) -> Self {
+        Self {
+            a,
+            b,
+            transform: Matrix::identity(),
+            scale: 1.,
+            turbulence: 5.,
+            noise: Perlin::default(),
+        }
+    }
+
+    /// Get a reference to the marble pattern's first color.
+    pub fn a(&self) -> &Color {
+        &self.a
+    }
+
+    /// Get a reference to the marble pattern's second color.
+    pub fn b(&self) -> &Color {
+        &self.b
+    }
+
+    /// Get the marble pattern's scale.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Multiply lookup points by `scale`. Composes with the pattern's
+    /// transform.
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Get how strongly noise bends the veins.
+    pub fn turbulence(&self) -> f64 {
+        self.turbulence
+    }
+
+    /// Set how strongly noise bends the veins; 0 gives straight bands.
+    pub fn with_turbulence(mut self, turbulence: f64) -> Self {
+        self.turbulence = turbulence;
+        self
+    }
+
+    /// Get the noise seed.
+    pub fn seed(&self) -> u64 {
+        self.noise.seed()
+    }
+
+    /// Reseed the noise, giving a different but repeatable set of veins.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.noise = Perlin::new(seed);
+        self
+    }
+}
+
+impl Pattern for Marble {
+    fn get_transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform;
+        self.clone()
+    }
+
+    fn pattern_at(&self, point: Tuple) -> Color {
+        let point = scale_point(point, self.scale);
+
+        let phase = point.x + self.turbulence * self.noise.turbulence(point, OCTAVES);
+        let fraction = (phase.sin() + 1.) / 2.;
+
+        self.a.clone() + (self.b.clone() - self.a.clone()) * fraction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::{color::Color, patterns::marble::Marble, tuple::Tuple};
+
+    use super::Pattern;
+
+    #[test]
+    fn marble_reaches_both_colors_at_the_sine_extremes() {
+        let pattern = Marble::new(Color::new_white(), Color::new_black()).with_turbulence(0.);
+
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(-FRAC_PI_2, 0., 0.)),
+            Color::new_white()
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(FRAC_PI_2, 0., 0.)),
+            Color::new_black()
+        );
+    }
+
+    #[test]
+    fn turbulent_marble_bends_the_veins_and_stays_between_its_colors() {
+        let a = Color::new(1., 0., 0.);
+        let b = Color::new(0., 0., 1.);
+        let pattern = Marble::new(a, b);
+        let straight = pattern.clone().with_turbulence(0.);
+        let (mut lowest, mut highest) = (1., 0.);
+        let mut bent = false;
+
+        for i in 0..20 {
+            for j in 0..20 {
+                let point = Tuple::point(i as f64 * 0.37, j as f64 * 0.29, 0.5);
+                let color = pattern.pattern_at(point);
+                // the blue channel is the fraction of the way from a to b
+                let fraction = color.blue();
+
+                assert!((0. ..=1.).contains(&fraction));
+                assert!((color.red() + fraction - 1.).abs() < 1e-9);
+                assert_eq!(color.green(), 0.);
+
+                lowest = f64::min(lowest, fraction);
+                highest = f64::max(highest, fraction);
+                bent |= color != straight.pattern_at(point);
+            }
+        }
+
+        assert!(lowest < 0.05, "never close to a: {}", lowest);
+        assert!(highest > 0.95, "never close to b: {}", highest);
+        assert!(bent);
+    }
+
+    #[test]
+    fn marble_is_deterministic_for_a_seed() {
+        let point = Tuple::point(0.3, 1.7, -2.2);
+        let pattern = Marble::new(Color::new_white(), Color::new_black()).with_seed(42);
+
+        assert_eq!(
+            pattern.pattern_at(point),
+            Marble::new(Color::new_white(), Color::new_black())
+                .with_seed(42)
+                .pattern_at(point)
+        );
+        assert_ne!(
+            pattern.pattern_at(point),
+            Marble::new(Color::new_white(), Color::new_black())
+                .with_seed(43)
+                .pattern_at(point)
+        );
+    }
+}
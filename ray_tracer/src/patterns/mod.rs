@@ -4,12 +4,17 @@ use crate::{color::Color, matrix::Matrix, shapes::Shape, tuple::Tuple};
 
 pub mod checkers;
 pub mod gradient;
+pub mod grid;
+pub mod image_texture;
+pub mod marble;
+pub mod noise;
 pub mod ring;
 pub mod stripe;
 pub mod test_pattern;
 
 use self::{
-    checkers::Checkers, gradient::Gradient, ring::Ring, stripe::Stripe, test_pattern::TestPattern,
+    checkers::Checkers, gradient::Gradient, grid::Grid, image_texture::ImageTexture,
+    marble::Marble, ring::Ring, stripe::Stripe, test_pattern::TestPattern,
 };
 
 pub trait Pattern {
@@ -17,6 +22,12 @@ pub trait Pattern {
     fn set_transform(&mut self, transform: Matrix<4>) -> Self;
 
     fn pattern_at(&self, point: Tuple) -> Color;
+    /// Like `pattern_at`, but averaged over a box of half-width `radius`
+    /// around the point, so fine patterns don't alias. Patterns without an
+    /// area-averaged form, or no radius, fall back to point sampling.
+    fn pattern_at_footprint(&self, point: Tuple, _radius: Option<f64>) -> Color {
+        self.pattern_at(point)
+    }
     fn pattern_at_shape(&self, object: Rc<dyn Shape>, world_point: Tuple) -> Color {
         let object_point = object.get_transform().inverse() * world_point;
         let pattern_point = self.get_transform().inverse() * object_point;
@@ -31,6 +42,9 @@ pub enum Patterns {
     Gradient(Gradient),
     Ring(Ring),
     Checkers(Checkers),
+    Grid(Grid),
+    ImageTexture(ImageTexture),
+    Marble(Marble),
     TestPattern(TestPattern),
 }
 
@@ -41,6 +55,9 @@ impl Pattern for Patterns {
             Patterns::Gradient(gradient) => gradient.get_transform(),
             Patterns::Ring(ring) => ring.get_transform(),
             Patterns::Checkers(checkers) => checkers.get_transform(),
+            Patterns::Grid(grid) => grid.get_transform(),
+            Patterns::ImageTexture(texture) => texture.get_transform(),
+            Patterns::Marble(marble) => marble.get_transform(),
             Patterns::TestPattern(test_pattern) => test_pattern.get_transform(),
         }
     }
@@ -51,6 +68,9 @@ impl Pattern for Patterns {
             Patterns::Gradient(gradient) => gradient.set_transform(transform).into(),
             Patterns::Ring(ring) => ring.set_transform(transform).into(),
             Patterns::Checkers(checkers) => checkers.set_transform(transform).into(),
+            Patterns::Grid(grid) => grid.set_transform(transform).into(),
+            Patterns::ImageTexture(texture) => texture.set_transform(transform).into(),
+            Patterns::Marble(marble) => marble.set_transform(transform).into(),
             Patterns::TestPattern(test_pattern) => test_pattern.set_transform(transform).into(),
         }
     }
@@ -61,9 +81,33 @@ impl Pattern for Patterns {
             Patterns::Gradient(gradient) => gradient.pattern_at(point),
             Patterns::Ring(ring) => ring.pattern_at(point),
             Patterns::Checkers(checkers) => checkers.pattern_at(point),
+            Patterns::Grid(grid) => grid.pattern_at(point),
+            Patterns::ImageTexture(texture) => texture.pattern_at(point),
+            Patterns::Marble(marble) => marble.pattern_at(point),
             Patterns::TestPattern(test_pattern) => test_pattern.pattern_at(point),
         }
     }
+
+    fn pattern_at_footprint(&self, point: Tuple, radius: Option<f64>) -> Color {
+        match self {
+            Patterns::Stripe(stripe) => stripe.pattern_at_footprint(point, radius),
+            Patterns::Checkers(checkers) => checkers.pattern_at_footprint(point, radius),
+            _ => self.pattern_at(point),
+        }
+    }
+}
+
+/// The fraction of `[lo, hi]` where `x.floor()` is odd, i.e. the box-filtered
+/// value of a square wave alternating every unit.
+pub(crate) fn odd_fraction(lo: f64, hi: f64) -> f64 {
+    let integral = |x: f64| (x / 2.).floor() + (x.rem_euclid(2.) - 1.).max(0.);
+
+    (integral(hi) - integral(lo)) / (hi - lo)
+}
+
+/// Multiply a pattern lookup point by a uniform scale.
+pub(crate) fn scale_point(point: Tuple, scale: f64) -> Tuple {
+    Tuple::point(point.x * scale, point.y * scale, point.z * scale)
 }
 
 impl From<Stripe> for Patterns {
@@ -90,6 +134,24 @@ impl From<Checkers> for Patterns {
     }
 }
 
+impl From<Grid> for Patterns {
+    fn from(grid: Grid) -> Self {
+        Patterns::Grid(grid)
+    }
+}
+
+impl From<ImageTexture> for Patterns {
+    fn from(texture: ImageTexture) -> Self {
+        Patterns::ImageTexture(texture)
+    }
+}
+
+impl From<Marble> for Patterns {
+    fn from(marble: Marble) -> Self {
+        Patterns::Marble(marble)
+    }
+}
+
 impl From<TestPattern> for Patterns {
     fn from(test_pattern: TestPattern) -> Self {
         Patterns::TestPattern(test_pattern)
@@ -0,0 +1,145 @@
+use crate::{sampling::Rng, tuple::Tuple};
+
+/// Gradient (Perlin) noise over a permutation table shuffled from a seed, so
+/// the same seed always produces the same field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Perlin {
+    seed: u64,
+    permutation: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut table: Vec<usize> = (0..256).collect();
+
+        for i in (1..table.len()).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+
+        let permutation = table.iter().chain(table.iter()).copied().collect();
+
+        Self { seed, permutation }
+    }
+
+    /// Get the seed the permutation table was shuffled from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Noise at `point`, roughly in `[-1, 1]` and zero on integer lattice
+    /// points.
+    pub fn noise(&self, point: Tuple) -> f64 {
+        let (xi, yi, zi) = (lattice(point.x), lattice(point.y), lattice(point.z));
+        let (x, y, z) = (
+            point.x - point.x.floor(),
+            point.y - point.y.floor(),
+            point.z - point.z.floor(),
+        );
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let p = &self.permutation;
+        let a = p[xi] + yi;
+        let aa = p[a] + zi;
+        let ab = p[a + 1] + zi;
+        let b = p[xi + 1] + yi;
+        let ba = p[b] + zi;
+        let bb = p[b + 1] + zi;
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1., y, z)),
+                lerp(u, grad(p[ab], x, y - 1., z), grad(p[bb], x - 1., y - 1., z)),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p[aa + 1], x, y, z - 1.),
+                    grad(p[ba + 1], x - 1., y, z - 1.),
+                ),
+                lerp(
+                    u,
+                    grad(p[ab + 1], x, y - 1., z - 1.),
+                    grad(p[bb + 1], x - 1., y - 1., z - 1.),
+                ),
+            ),
+        )
+    }
+
+    /// Sum of `octaves` layers of absolute noise, each at twice the frequency
+    /// and half the amplitude of the last.
+    pub fn turbulence(&self, point: Tuple, octaves: usize) -> f64 {
+        let mut sum = 0.;
+        let mut frequency = 1.;
+
+        for _ in 0..octaves {
+            let sample = Tuple::point(
+                point.x * frequency,
+                point.y * frequency,
+                point.z * frequency,
+            );
+            sum += self.noise(sample).abs() / frequency;
+            frequency *= 2.;
+        }
+
+        sum
+    }
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+fn lattice(value: f64) -> usize {
+    (value.floor() as i64).rem_euclid(256) as usize
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tuple::Tuple;
+
+    use super::Perlin;
+
+    #[test]
+    fn noise_is_zero_on_lattice_points() {
+        let perlin = Perlin::new(7);
+
+        assert_eq!(perlin.noise(Tuple::point(3., -2., 5.)), 0.);
+    }
+
+    #[test]
+    fn noise_is_deterministic_for_a_seed() {
+        let point = Tuple::point(1.3, 2.7, -0.4);
+
+        assert_eq!(Perlin::new(7).noise(point), Perlin::new(7).noise(point));
+        assert_ne!(Perlin::new(7).noise(point), Perlin::new(8).noise(point));
+    }
+}
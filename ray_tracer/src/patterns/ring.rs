@@ -1,12 +1,13 @@
 use crate::{color::Color, matrix::Matrix, tuple::Tuple};
 
-use super::Pattern;
+use super::{scale_point, Pattern};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Ring {
     a: Color,
     b: Color,
     transform: Matrix<4>,
+    scale: f64,
 }
 
 impl Ring {
@@ -15,8 +16,31 @@ impl Ring {
             a,
             b,
             transform: Matrix::identity(),
+            scale: 1.,
         }
     }
+
+    /// Get a reference to the ring pattern's first color.
+    pub fn a(&self) -> &Color {
+        &self.a
+    }
+
+    /// Get a reference to the ring pattern's second color.
+    pub fn b(&self) -> &Color {
+        &self.b
+    }
+
+    /// Get the ring pattern's scale.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Multiply lookup points by `scale`, so 2 repeats the pattern twice as
+    /// often. Composes with the pattern's transform.
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
 }
 
 impl Pattern for Ring {
@@ -30,6 +54,8 @@ impl Pattern for Ring {
     }
 
     fn pattern_at(&self, point: Tuple) -> Color {
+        let point = scale_point(point, self.scale);
+
         if (point.x.powf(2.) + point.z.powf(2.)).sqrt().floor() % 2.0 == 0.0 {
             self.a.clone()
         } else {
@@ -1,12 +1,13 @@
 use crate::{color::Color, matrix::Matrix, tuple::Tuple};
 
-use super::Pattern;
+use super::{odd_fraction, scale_point, Pattern};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Stripe {
     a: Color,
     b: Color,
     transform: Matrix<4>,
+    scale: f64,
 }
 
 impl Stripe {
@@ -15,8 +16,31 @@ impl Stripe {
             a,
             b,
             transform: Matrix::identity(),
+            scale: 1.,
         }
     }
+
+    /// Get a reference to the stripe pattern's first color.
+    pub fn a(&self) -> &Color {
+        &self.a
+    }
+
+    /// Get a reference to the stripe pattern's second color.
+    pub fn b(&self) -> &Color {
+        &self.b
+    }
+
+    /// Get the stripe pattern's scale.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Multiply lookup points by `scale`, so 2 repeats the pattern twice as
+    /// often. Composes with the pattern's transform.
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
 }
 
 impl Pattern for Stripe {
@@ -30,12 +54,28 @@ impl Pattern for Stripe {
     }
 
     fn pattern_at(&self, point: Tuple) -> Color {
+        let point = scale_point(point, self.scale);
+
         if point.x.floor() % 2.0 == 0.0 {
             self.a.clone()
         } else {
             self.b.clone()
         }
     }
+
+    fn pattern_at_footprint(&self, point: Tuple, radius: Option<f64>) -> Color {
+        match radius {
+            Some(r) if r > 0. => {
+                let point = scale_point(point, self.scale);
+                let r = r * self.scale;
+
+                let odd = odd_fraction(point.x - r, point.x + r);
+
+                self.a.clone() * (1. - odd) + self.b.clone() * odd
+            }
+            _ => self.pattern_at(point),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -152,4 +192,17 @@ mod tests {
 
         assert_eq!(c, Color::new_white());
     }
+
+    #[test]
+    fn a_footprint_across_a_stripe_boundary_blends_the_colors() {
+        let pattern = Stripe::new(Color::new_white(), Color::new_black());
+
+        let blended = pattern.pattern_at_footprint(Tuple::point(1., 0., 0.), Some(0.25));
+        let inside = pattern.pattern_at_footprint(Tuple::point(0.5, 0., 0.), Some(0.25));
+        let point = pattern.pattern_at_footprint(Tuple::point(0.9, 0., 0.), None);
+
+        assert_eq!(blended, Color::new(0.5, 0.5, 0.5));
+        assert_eq!(inside, Color::new_white());
+        assert_eq!(point, Color::new_white());
+    }
 }
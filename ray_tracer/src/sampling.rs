@@ -0,0 +1,115 @@
+use std::f64::consts::PI;
+
+use crate::tuple::Tuple;
+
+/// A small, seedable xorshift random number generator, so renders that
+/// sample stay reproducible without pulling in a dependency.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero
+        Self { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// A uniformly distributed number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Two unit vectors that, together with `normal`, form an orthonormal basis.
+pub fn tangent_frame(normal: Tuple) -> (Tuple, Tuple) {
+    let helper = if normal.x.abs() < 0.9 {
+        Tuple::vector(1., 0., 0.)
+    } else {
+        Tuple::vector(0., 1., 0.)
+    };
+    let tangent = Tuple::cross(&normal, &helper).normalize();
+    let bitangent = Tuple::cross(&normal, &tangent);
+
+    (tangent, bitangent)
+}
+
+/// A uniformly distributed point in the unit disk, using the concentric
+/// mapping so that evenly spread inputs stay evenly spread.
+pub fn uniform_disk(rng: &mut Rng) -> (f64, f64) {
+    let a = 2. * rng.next_f64() - 1.;
+    let b = 2. * rng.next_f64() - 1.;
+
+    if a == 0. && b == 0. {
+        return (0., 0.);
+    }
+
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, PI / 4. * (b / a))
+    } else {
+        (b, PI / 2. - PI / 4. * (a / b))
+    };
+
+    (r * theta.cos(), r * theta.sin())
+}
+
+/// A direction in the hemisphere around `normal`, more likely near the
+/// normal in proportion to the cosine of the angle to it.
+pub fn cosine_weighted_hemisphere(normal: Tuple, rng: &mut Rng) -> Tuple {
+    let (x, y) = uniform_disk(rng);
+    let z = (1. - x * x - y * y).max(0.).sqrt();
+    let (tangent, bitangent) = tangent_frame(normal);
+
+    (tangent * x + bitangent * y + normal * z).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tuple::Tuple;
+
+    use super::{cosine_weighted_hemisphere, uniform_disk, Rng};
+
+    #[test]
+    fn the_rng_is_reproducible_and_in_range() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+
+        for _ in 0..100 {
+            let x = a.next_f64();
+
+            assert_eq!(x, b.next_f64());
+            assert!((0. ..1.).contains(&x));
+        }
+    }
+
+    #[test]
+    fn disk_samples_fall_within_the_unit_circle() {
+        let mut rng = Rng::new(7);
+
+        for _ in 0..1000 {
+            let (x, y) = uniform_disk(&mut rng);
+
+            assert!(x * x + y * y <= 1. + 1e-12);
+        }
+    }
+
+    #[test]
+    fn hemisphere_samples_point_away_from_the_surface() {
+        let mut rng = Rng::new(7);
+        let normal = Tuple::vector(1., 2., -3.).normalize();
+
+        for _ in 0..1000 {
+            let direction = cosine_weighted_hemisphere(normal, &mut rng);
+
+            assert!(Tuple::dot(&direction, &normal) >= 0.);
+            assert!((direction.magnitude() - 1.).abs() < 1e-9);
+        }
+    }
+}
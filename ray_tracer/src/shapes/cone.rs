@@ -1,10 +1,10 @@
-use std::{mem::swap, rc::Rc};
+use std::{any::Any, mem::swap, rc::Rc};
 
 use uuid::Uuid;
 
 use crate::{
-    constants::EPSILON, intersections::Intersection, material::Material, matrix::Matrix, ray::Ray,
-    tuple::Tuple, utils::fuzzy_equal::fuzzy_equal,
+    bounds::Bounds, constants::EPSILON, intersections::Intersection, material::Material,
+    matrix::Matrix, ray::Ray, tuple::Tuple, utils::fuzzy_equal::fuzzy_equal,
 };
 
 use super::Shape;
@@ -12,6 +12,7 @@ use super::Shape;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Cone {
     id: Uuid,
+    tag: Option<String>,
     transform: Matrix<4>,
     material: Material,
     minimum: f64,
@@ -29,7 +30,8 @@ impl Cone {
     ) -> Self {
         Self {
             id: Uuid::new_v4(),
-            transform,
+            tag: None,
+            transform: transform.to_invertible(),
             material,
             minimum,
             maximum,
@@ -37,6 +39,10 @@ impl Cone {
         }
     }
 
+    pub fn new_glass() -> Self {
+        Self::default().with_material(Material::glass())
+    }
+
     /// Get a reference to the cone's minimum.
     pub fn minimum(&self) -> f64 {
         self.minimum
@@ -76,10 +82,25 @@ impl Cone {
     }
 
     pub fn set_transform(&mut self, transform: Matrix<4>) -> Self {
-        self.transform = transform;
+        self.transform = transform.to_invertible();
         self.clone()
     }
 
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform.to_invertible();
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
     pub fn intersect_caps(&self, ray: &Ray, xs: &mut Vec<Intersection>) {
         fn check_cap(ray: &Ray, t: f64, y: f64) -> bool {
             let x = ray.origin.x + t * ray.direction.x;
@@ -121,6 +142,18 @@ impl Shape for Cone {
         self.id
     }
 
+    fn get_tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn get_material(&self) -> Material {
         self.material.clone()
     }
@@ -134,7 +167,7 @@ impl Shape for Cone {
     }
 
     fn set_transform(&mut self, transform: Matrix<4>) {
-        self.transform = transform;
+        self.transform = transform.to_invertible();
     }
 
     fn intersection(&self, t: f64) -> Intersection {
@@ -178,7 +211,7 @@ impl Shape for Cone {
 
         self.intersect_caps(ray, &mut xs);
 
-        if xs.len() == 0 {
+        if xs.is_empty() {
             None
         } else {
             Some(xs)
@@ -195,6 +228,9 @@ impl Shape for Cone {
         } else {
             let mut y = (point.x.powf(2.) + point.z.powf(2.)).sqrt();
 
+            // The double cone has a real crease at the apex, where the two
+            // nappes meet, so the normal flips there on purpose. Along each
+            // nappe it is continuous.
             if point.y > 0. {
                 y = -y;
             }
@@ -202,6 +238,15 @@ impl Shape for Cone {
             return Tuple::vector(point.x, y, point.z);
         }
     }
+
+    fn bounds(&self) -> Bounds {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+
+        Bounds::new(
+            Tuple::point(-limit, self.minimum, -limit),
+            Tuple::point(limit, self.maximum, limit),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -292,6 +337,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn a_ray_entering_the_open_end_of_a_bounded_cone_hits_only_the_side() {
+        let cone = Cone::default().set_minimum(0.).set_maximum(1.);
+        let r = Ray::new(
+            Tuple::point(0., 2., 0.),
+            Tuple::vector(0.25, -1., 0.).normalize(),
+        );
+
+        let xs = cone.local_intersect(&r).unwrap();
+
+        assert_eq!(xs.len(), 1);
+        assert!(fuzzy_equal(r.position(xs[0].t).y, 0.4));
+    }
+
     #[test]
     fn computing_the_normal_vector_on_a_cone() {
         let cone = Cone::default()
@@ -314,4 +373,27 @@ mod tests {
             assert_eq!(n, normal);
         }
     }
+
+    #[test]
+    fn a_bounded_cone_has_a_bounding_box() {
+        let cone = Cone::default().set_minimum(-5.).set_maximum(3.);
+
+        let b = cone.bounds();
+
+        assert_eq!(b.min, Tuple::point(-5., -5., -5.));
+        assert_eq!(b.max, Tuple::point(5., 3., 5.));
+    }
+
+    #[test]
+    fn cone_normals_are_continuous_along_a_nappe_and_mirror_at_the_apex() {
+        let cone = Cone::default();
+        let r = 0.001;
+
+        let above = cone.local_normal_at(Tuple::point(r, r, 0.)).normalize();
+        let below = cone.local_normal_at(Tuple::point(r, -r, 0.)).normalize();
+        let far_above = cone.local_normal_at(Tuple::point(2., 2., 0.)).normalize();
+
+        assert_eq!(above, far_above);
+        assert_eq!(above, Tuple::vector(below.x, -below.y, below.z));
+    }
 }
@@ -1,10 +1,15 @@
-use std::rc::Rc;
+use std::{any::Any, rc::Rc};
 
 use uuid::Uuid;
 
 use crate::{
-    constants::EPSILON, intersections::Intersection, material::Material, matrix::Matrix,
+    bounds::Bounds,
+    constants::EPSILON,
+    intersections::Intersection,
+    material::Material,
+    matrix::Matrix,
     tuple::Tuple,
+    utils::ordering::{max_f64, min_f64},
 };
 
 use super::Shape;
@@ -12,6 +17,7 @@ use super::Shape;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Cube {
     id: Uuid,
+    tag: Option<String>,
     pub transform: Matrix<4>,
     pub material: Material,
 }
@@ -20,21 +26,41 @@ impl Cube {
     pub fn new(transform: Matrix<4>, material: Material) -> Self {
         Self {
             id: Uuid::new_v4(),
-            transform,
+            tag: None,
+            transform: transform.to_invertible(),
             material,
         }
     }
 
+    pub fn new_glass() -> Self {
+        Self::default().with_material(Material::glass())
+    }
+
     pub fn set_material(&mut self, material: Material) -> Self {
         self.material = material;
         self.clone()
     }
 
     pub fn set_transform(&mut self, transform: Matrix<4>) -> Self {
-        self.transform = transform;
+        self.transform = transform.to_invertible();
         self.clone()
     }
 
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform.to_invertible();
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
     fn check_axis(origin: f64, direction: f64) -> (f64, f64) {
         let tmin_numerator = -1. - origin;
         let tmax_numerator = 1. - origin;
@@ -69,6 +95,18 @@ impl Shape for Cube {
         self.id
     }
 
+    fn get_tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn get_material(&self) -> Material {
         self.material.clone()
     }
@@ -82,7 +120,7 @@ impl Shape for Cube {
     }
 
     fn set_transform(&mut self, transform: Matrix<4>) {
-        self.transform = transform;
+        self.transform = transform.to_invertible();
     }
 
     fn intersection(&self, t: f64) -> Intersection {
@@ -94,37 +132,31 @@ impl Shape for Cube {
         let (ytmin, ytmax) = Cube::check_axis(ray.origin.y, ray.direction.y);
         let (ztmin, ztmax) = Cube::check_axis(ray.origin.z, ray.direction.z);
 
-        let tmin_arr = [xtmin, ytmin, ztmin];
-        let tmax_arr = [xtmax, ytmax, ztmax];
-
-        let tmin = tmin_arr.iter().max_by(|a, b| a.partial_cmp(b).unwrap());
-        let tmax = tmax_arr.iter().min_by(|a, b| a.partial_cmp(b).unwrap());
+        let tmin = max_f64(&[xtmin, ytmin, ztmin]);
+        let tmax = min_f64(&[xtmax, ytmax, ztmax]);
 
         if tmin > tmax {
             None
         } else {
-            Some(vec![
-                self.intersection(*tmin.unwrap()),
-                self.intersection(*tmax.unwrap()),
-            ])
+            Some(vec![self.intersection(tmin), self.intersection(tmax)])
         }
     }
 
     fn local_normal_at(&self, point: Tuple) -> Tuple {
-        let maxc_arr = [point.x.abs(), point.y.abs(), point.z.abs()];
-        let maxc = maxc_arr
-            .iter()
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
+        let maxc = max_f64(&[point.x.abs(), point.y.abs(), point.z.abs()]);
 
-        if maxc == &point.x.abs() {
+        if maxc == point.x.abs() {
             return Tuple::vector(point.x, 0., 0.);
-        } else if maxc == &point.y.abs() {
+        } else if maxc == point.y.abs() {
             return Tuple::vector(0., point.y, 0.);
         }
 
         Tuple::vector(0., 0., point.z)
     }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+    }
 }
 
 #[cfg(test)]
@@ -1,10 +1,10 @@
-use std::{mem::swap, rc::Rc};
+use std::{any::Any, mem::swap, rc::Rc};
 
 use uuid::Uuid;
 
 use crate::{
-    constants::EPSILON, intersections::Intersection, material::Material, matrix::Matrix, ray::Ray,
-    tuple::Tuple, utils::fuzzy_equal::fuzzy_equal,
+    bounds::Bounds, constants::EPSILON, intersections::Intersection, material::Material,
+    matrix::Matrix, ray::Ray, tuple::Tuple, utils::fuzzy_equal::fuzzy_equal,
 };
 
 use super::Shape;
@@ -12,6 +12,7 @@ use super::Shape;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Cylinder {
     id: Uuid,
+    tag: Option<String>,
     transform: Matrix<4>,
     material: Material,
     minimum: f64,
@@ -29,7 +30,8 @@ impl Cylinder {
     ) -> Self {
         Self {
             id: Uuid::new_v4(),
-            transform,
+            tag: None,
+            transform: transform.to_invertible(),
             material,
             minimum,
             maximum,
@@ -37,6 +39,10 @@ impl Cylinder {
         }
     }
 
+    pub fn new_glass() -> Self {
+        Self::default().with_material(Material::glass())
+    }
+
     /// Get a reference to the cylinder's minimum.
     pub fn minimum(&self) -> f64 {
         self.minimum
@@ -76,10 +82,25 @@ impl Cylinder {
     }
 
     pub fn set_transform(&mut self, transform: Matrix<4>) -> Self {
-        self.transform = transform;
+        self.transform = transform.to_invertible();
         self.clone()
     }
 
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform.to_invertible();
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
     pub fn intersect_caps(&self, ray: &Ray, xs: &mut Vec<Intersection>) {
         fn check_cap(ray: &Ray, t: f64) -> bool {
             let x = ray.origin.x + t * ray.direction.x;
@@ -121,6 +142,18 @@ impl Shape for Cylinder {
         self.id
     }
 
+    fn get_tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn get_material(&self) -> Material {
         self.material.clone()
     }
@@ -134,7 +167,7 @@ impl Shape for Cylinder {
     }
 
     fn set_transform(&mut self, transform: Matrix<4>) {
-        self.transform = transform;
+        self.transform = transform.to_invertible();
     }
 
     fn intersection(&self, t: f64) -> Intersection {
@@ -173,7 +206,7 @@ impl Shape for Cylinder {
 
         self.intersect_caps(ray, &mut xs);
 
-        if xs.len() == 0 {
+        if xs.is_empty() {
             None
         } else {
             Some(xs)
@@ -191,11 +224,20 @@ impl Shape for Cylinder {
             return Tuple::vector(point.x, 0., point.z);
         }
     }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::new(
+            Tuple::point(-1., self.minimum, -1.),
+            Tuple::point(1., self.maximum, 1.),
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{ray::Ray, shapes::Shape, tuple::Tuple, utils::fuzzy_equal::fuzzy_equal};
+    use crate::{
+        constants::EPSILON, ray::Ray, shapes::Shape, tuple::Tuple, utils::fuzzy_equal::fuzzy_equal,
+    };
 
     use super::Cylinder;
 
@@ -295,6 +337,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn a_ray_through_the_open_ends_of_a_bounded_cylinder_misses_it() {
+        let cyl = Cylinder::default().set_minimum(1.).set_maximum(2.);
+        let r = Ray::new(Tuple::point(0.5, 3., 0.), Tuple::vector(0., -1., 0.));
+
+        assert!(cyl.local_intersect(&r).is_none());
+    }
+
+    #[test]
+    fn a_ray_entering_an_open_end_hits_only_the_side() {
+        let cyl = Cylinder::default().set_minimum(1.).set_maximum(2.);
+        let r = Ray::new(
+            Tuple::point(0., 2.5, 0.),
+            Tuple::vector(1., -1., 0.).normalize(),
+        );
+
+        let xs = cyl.local_intersect(&r).unwrap();
+
+        assert_eq!(xs.len(), 1);
+        assert!(fuzzy_equal(xs[0].t, 2.0_f64.sqrt()));
+        assert!(fuzzy_equal(r.position(xs[0].t).y, 1.5));
+    }
+
+    #[test]
+    fn the_side_of_an_open_cylinder_ends_exactly_at_its_bounds() {
+        let cyl = Cylinder::default().set_minimum(1.).set_maximum(2.);
+
+        let examples = vec![(2. - EPSILON, 2), (2., 0), (1. + EPSILON, 2), (1., 0)];
+
+        for (y, count) in examples.into_iter() {
+            let r = Ray::new(Tuple::point(0., y, -5.), Tuple::vector(0., 0., 1.));
+
+            let xs = cyl.local_intersect(&r);
+
+            assert_eq!(xs.unwrap_or(vec![]).len(), count);
+        }
+    }
+
     #[test]
     fn the_default_closed_value_for_a_cylinder() {
         let cyl = Cylinder::default();
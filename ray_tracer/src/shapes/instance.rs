@@ -0,0 +1,256 @@
+use std::{any::Any, rc::Rc, sync::Arc};
+
+use uuid::Uuid;
+
+use crate::{
+    bounds::Bounds, intersections::Intersection, material::Material, matrix::Matrix, ray::Ray,
+    tuple::Tuple,
+};
+
+use super::Shape;
+
+/// A shared shape placed into the world with its own transform and material,
+/// so many copies of the same geometry don't need to be cloned. The shape's
+/// bounds are cached, and rays that miss them never reach the shape.
+#[derive(Debug, Clone)]
+pub struct Instance {
+    id: Uuid,
+    tag: Option<String>,
+    shape: Arc<dyn Shape + Send>,
+    bounds: Bounds,
+    transform: Matrix<4>,
+    material: Material,
+}
+
+impl Instance {
+    /// Create an instance of the shape. The transform is applied on top of the
+    /// shape's own transform and the material starts as a copy of the shape's.
+    pub fn new(shape: Arc<dyn Shape + Send>, transform: Matrix<4>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            tag: None,
+            material: shape.get_material(),
+            bounds: shape.parent_space_bounds(),
+            shape,
+            transform: transform.to_invertible(),
+        }
+    }
+
+    /// Get a reference to the instanced shape.
+    pub fn shape(&self) -> &Arc<dyn Shape + Send> {
+        &self.shape
+    }
+
+    pub fn set_material(&mut self, material: Material) -> Self {
+        self.material = material;
+        self.clone()
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform.to_invertible();
+        self.clone()
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform.to_invertible();
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+}
+
+impl Shape for Instance {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn get_tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_material(&self) -> Material {
+        self.material.clone()
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    fn get_transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix<4>) {
+        self.transform = transform.to_invertible();
+    }
+
+    fn intersection(&self, t: f64) -> Intersection {
+        Intersection::new(t, Rc::new(self.clone()))
+    }
+
+    fn local_intersect(&self, local_ray: &Ray) -> Option<Vec<Intersection>> {
+        if !self.bounds.intersects(local_ray) {
+            return None;
+        }
+
+        // re-target the hits at the instance so shading goes through its transform
+        let xs = self.shape.intersect(local_ray)?;
+
+        Some(xs.iter().map(|i| self.intersection(i.t)).collect())
+    }
+
+    fn local_normal_at(&self, local_point: Tuple) -> Tuple {
+        let inverse = self.shape.get_transform().inverse();
+        let mut normal = inverse.transpose() * self.shape.local_normal_at(inverse * local_point);
+
+        normal.w = 0.;
+
+        normal
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        any::Any,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use uuid::Uuid;
+
+    use crate::{
+        bounds::Bounds,
+        intersections::Intersection,
+        material::Material,
+        matrix::Matrix,
+        ray::Ray,
+        shapes::{instance::Instance, sphere::Sphere, triangle::Triangle, Shape},
+        tuple::Tuple,
+    };
+
+    /// A triangle that counts how often it is intersected.
+    #[derive(Debug)]
+    struct CountingTriangle {
+        triangle: Triangle,
+        intersections: AtomicUsize,
+    }
+
+    impl Shape for CountingTriangle {
+        fn id(&self) -> Uuid {
+            self.triangle.id()
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn get_material(&self) -> Material {
+            self.triangle.get_material()
+        }
+
+        fn set_material(&mut self, material: Material) {
+            Shape::set_material(&mut self.triangle, material);
+        }
+
+        fn get_transform(&self) -> Matrix<4> {
+            self.triangle.get_transform()
+        }
+
+        fn set_transform(&mut self, transform: Matrix<4>) {
+            Shape::set_transform(&mut self.triangle, transform);
+        }
+
+        fn get_tag(&self) -> Option<&str> {
+            None
+        }
+
+        fn set_tag(&mut self, _tag: Option<String>) {}
+
+        fn intersection(&self, t: f64) -> Intersection {
+            self.triangle.intersection(t)
+        }
+
+        fn local_intersect(&self, local_ray: &Ray) -> Option<Vec<Intersection>> {
+            self.intersections.fetch_add(1, Ordering::Relaxed);
+            self.triangle.local_intersect(local_ray)
+        }
+
+        fn local_normal_at(&self, local_point: Tuple) -> Tuple {
+            self.triangle.local_normal_at(local_point)
+        }
+
+        fn bounds(&self) -> Bounds {
+            self.triangle.bounds()
+        }
+    }
+
+    #[test]
+    fn instances_of_one_shape_intersect_at_their_own_positions() {
+        let sphere = Arc::new(Sphere::default());
+        let a = Instance::new(sphere.clone(), Matrix::identity().translation(0., 0., 2.));
+        let b = Instance::new(sphere, Matrix::identity().translation(0., 0., 4.));
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+
+        let xs_a = a.intersect(&r).unwrap();
+        let xs_b = b.intersect(&r).unwrap();
+
+        assert_eq!((xs_a[0].t, xs_a[1].t), (6., 8.));
+        assert_eq!((xs_b[0].t, xs_b[1].t), (8., 10.));
+        assert_eq!(xs_a[0].object.id(), a.id());
+    }
+
+    #[test]
+    fn the_normal_on_an_instance_combines_both_transforms() {
+        let sphere =
+            Arc::new(Sphere::default().set_transform(Matrix::identity().translation(0., 1., 0.)));
+        let instance = Instance::new(sphere, Matrix::identity().translation(5., 0., 0.));
+
+        let n = instance.normal_at(Tuple::point(5., 1.70711, -0.70711));
+
+        assert_eq!(n, Tuple::vector(0., 0.70711, -0.70711));
+    }
+
+    #[test]
+    fn rays_missing_the_cached_bounds_skip_the_shape() {
+        let triangle = Arc::new(CountingTriangle {
+            triangle: Triangle::new(
+                Tuple::point(0., 1., 0.),
+                Tuple::point(-1., 0., 0.),
+                Tuple::point(1., 0., 0.),
+            ),
+            intersections: AtomicUsize::new(0),
+        });
+        let instance = Instance::new(triangle.clone(), Matrix::identity());
+
+        let miss = Ray::new(Tuple::point(5., 5., -2.), Tuple::vector(0., 0., 1.));
+        assert!(instance.intersect(&miss).is_none());
+        assert_eq!(triangle.intersections.load(Ordering::Relaxed), 0);
+
+        let hit = Ray::new(Tuple::point(0., 0.5, -2.), Tuple::vector(0., 0., 1.));
+        assert_eq!(instance.intersect(&hit).unwrap().len(), 1);
+        assert_eq!(triangle.intersections.load(Ordering::Relaxed), 1);
+    }
+}
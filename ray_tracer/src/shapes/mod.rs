@@ -1,28 +1,44 @@
-use std::fmt::Debug;
+use std::{any::Any, fmt::Debug};
 use uuid::Uuid;
 
 use crate::{
-    intersections::Intersection, material::Material, matrix::Matrix, ray::Ray, tuple::Tuple,
+    bounds::Bounds, constants::EPSILON, intersections::Intersection, material::Material,
+    matrix::Matrix, patterns::Patterns, ray::Ray, tuple::Tuple,
 };
 
 pub mod cone;
 pub mod cube;
 pub mod cylinder;
+pub mod instance;
 pub mod plane;
 pub mod sphere;
+pub mod triangle;
 
-pub trait Shape: Sync + Debug {
+pub trait Shape: Send + Sync + Debug {
     fn id(&self) -> Uuid;
+    /// Access the concrete shape, e.g. to downcast it when exporting a scene.
+    fn as_any(&self) -> &dyn Any;
     // materials
     fn get_material(&self) -> Material;
     /// Set the Shape's material.
     fn set_material(&mut self, material: Material);
+    /// Pattern the Shape's surface, keeping the rest of its material.
+    fn set_pattern(&mut self, pattern: Patterns) {
+        let material = self.get_material().set_pattern(pattern);
+
+        self.set_material(material);
+    }
 
     // transform
     fn get_transform(&self) -> Matrix<4>;
     /// Set the Shape's transform.
     fn set_transform(&mut self, transform: Matrix<4>);
 
+    // tags
+    fn get_tag(&self) -> Option<&str>;
+    /// Tag the Shape, e.g. to render it on its own layer.
+    fn set_tag(&mut self, tag: Option<String>);
+
     // intersection
     fn intersection(&self, t: f64) -> Intersection;
     fn local_intersect(&self, local_ray: &Ray) -> Option<Vec<Intersection>>;
@@ -35,13 +51,59 @@ pub trait Shape: Sync + Debug {
     fn local_normal_at(&self, local_point: Tuple) -> Tuple;
     fn normal_at(&self, world_point: Tuple) -> Tuple {
         let local_point = self.get_transform().inverse() * world_point;
-        let local_normal = self.local_normal_at(local_point);
+        let mut local_normal = self.local_normal_at(local_point);
+
+        if self.get_material().get_flip_normals() {
+            local_normal = -local_normal;
+        }
+
+        self.normal_to_world(local_normal)
+    }
+    /// Convert a normal from object space to a normalized world-space normal.
+    fn normal_to_world(&self, local_normal: Tuple) -> Tuple {
         let mut world_normal = self.get_transform().inverse().transpose() * local_normal;
 
         world_normal.w = 0.;
 
         world_normal.normalize()
     }
+
+    // shading
+    /// How far shading points are pushed off the surface to avoid shadow
+    /// acne, see `ComputedIntersection::over_point`. Rounding grows with the
+    /// scale, e.g. far out on a plane scaled into a huge floor, so the offset
+    /// grows along with it; shapes at unit scale or smaller use `EPSILON`.
+    fn shadow_offset(&self) -> f64 {
+        EPSILON * max_axis_scale(self.get_transform()).max(1.)
+    }
+
+    // texture coordinates
+    /// Map a point on the surface (in object space) to `(u, v)` texture
+    /// coordinates in `[0, 1)`. Shapes without a mapping return `None`.
+    fn local_uv_at(&self, _local_point: Tuple) -> Option<(f64, f64)> {
+        None
+    }
+
+    // bounds
+    /// The shape's axis-aligned bounding box in object space.
+    fn bounds(&self) -> Bounds;
+    /// The shape's bounding box after applying its transform.
+    fn parent_space_bounds(&self) -> Bounds {
+        self.bounds().transform(self.get_transform())
+    }
+}
+
+/// The largest factor by which `transform` stretches a unit vector along one
+/// of the axes.
+fn max_axis_scale(transform: Matrix<4>) -> f64 {
+    [
+        Tuple::vector(1., 0., 0.),
+        Tuple::vector(0., 1., 0.),
+        Tuple::vector(0., 0., 1.),
+    ]
+    .iter()
+    .map(|axis| (transform * *axis).magnitude())
+    .fold(0., f64::max)
 }
 
 impl PartialEq for dyn Shape {
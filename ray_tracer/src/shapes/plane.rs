@@ -1,10 +1,10 @@
-use std::rc::Rc;
+use std::{any::Any, rc::Rc};
 
 use uuid::Uuid;
 
 use crate::{
-    constants::EPSILON, intersections::Intersection, material::Material, matrix::Matrix,
-    tuple::Tuple,
+    bounds::Bounds, constants::EPSILON, intersections::Intersection, material::Material,
+    matrix::Matrix, patterns::image_texture::UvMapping, tuple::Tuple,
 };
 
 use super::Shape;
@@ -12,6 +12,7 @@ use super::Shape;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Plane {
     id: Uuid,
+    tag: Option<String>,
     pub transform: Matrix<4>,
     pub material: Material,
 }
@@ -20,20 +21,40 @@ impl Plane {
     pub fn new(transform: Matrix<4>, material: Material) -> Self {
         Self {
             id: Uuid::new_v4(),
-            transform,
+            tag: None,
+            transform: transform.to_invertible(),
             material,
         }
     }
 
+    pub fn new_glass() -> Self {
+        Self::default().with_material(Material::glass())
+    }
+
     pub fn set_material(&mut self, material: Material) -> Self {
         self.material = material;
         self.clone()
     }
 
     pub fn set_transform(&mut self, transform: Matrix<4>) -> Self {
-        self.transform = transform;
+        self.transform = transform.to_invertible();
         self.clone()
     }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform.to_invertible();
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
 }
 
 impl Default for Plane {
@@ -47,6 +68,18 @@ impl Shape for Plane {
         self.id
     }
 
+    fn get_tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn get_material(&self) -> Material {
         self.material.clone()
     }
@@ -60,7 +93,7 @@ impl Shape for Plane {
     }
 
     fn set_transform(&mut self, transform: Matrix<4>) {
-        self.transform = transform;
+        self.transform = transform.to_invertible();
     }
 
     fn intersection(&self, t: f64) -> Intersection {
@@ -80,12 +113,25 @@ impl Shape for Plane {
     fn local_normal_at(&self, _: Tuple) -> Tuple {
         Tuple::vector(0., 1., 0.)
     }
+
+    fn local_uv_at(&self, local_point: Tuple) -> Option<(f64, f64)> {
+        Some(UvMapping::Planar.uv_at(local_point))
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::new(
+            Tuple::point(f64::NEG_INFINITY, 0., f64::NEG_INFINITY),
+            Tuple::point(f64::INFINITY, 0., f64::INFINITY),
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use crate::{
+        color::Color,
+        patterns::{checkers::Checkers, Patterns},
         ray::Ray,
         shapes::{plane::Plane, Shape},
         tuple::Tuple,
@@ -147,4 +193,15 @@ mod tests {
         assert_eq!(xs.as_ref().unwrap()[0].t, 1.);
         assert_eq!(xs.unwrap()[0].object.id(), p.id());
     }
+
+    #[test]
+    fn setting_a_pattern_without_building_a_material() {
+        let checkers: Patterns = Checkers::new(Color::new_white(), Color::new_black()).into();
+        let mut p = Plane::default();
+
+        p.set_pattern(checkers.clone());
+
+        assert_eq!(p.material.get_pattern(), Some(&checkers));
+        assert_eq!(p.material.get_diffuse(), 0.9);
+    }
 }
@@ -1,14 +1,18 @@
-use std::rc::Rc;
+use std::{any::Any, rc::Rc};
 
 use uuid::Uuid;
 
-use crate::{intersections::Intersection, material::Material, matrix::Matrix, tuple::Tuple};
+use crate::{
+    bounds::Bounds, intersections::Intersection, material::Material, matrix::Matrix,
+    patterns::image_texture::UvMapping, tuple::Tuple,
+};
 
 use super::Shape;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Sphere {
     id: Uuid,
+    tag: Option<String>,
     pub transform: Matrix<4>,
     pub material: Material,
 }
@@ -17,17 +21,14 @@ impl Sphere {
     pub fn new(transform: Matrix<4>, material: Material) -> Self {
         Self {
             id: Uuid::new_v4(),
-            transform,
+            tag: None,
+            transform: transform.to_invertible(),
             material,
         }
     }
 
     pub fn new_glass() -> Self {
-        Self::default().set_material(
-            Material::default()
-                .set_transparency(1.)
-                .set_refractive_index(1.5),
-        )
+        Self::default().with_material(Material::glass())
     }
 
     pub fn set_material(&mut self, material: Material) -> Self {
@@ -36,9 +37,24 @@ impl Sphere {
     }
 
     pub fn set_transform(&mut self, transform: Matrix<4>) -> Self {
-        self.transform = transform;
+        self.transform = transform.to_invertible();
         self.clone()
     }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform.to_invertible();
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
 }
 
 impl Default for Sphere {
@@ -52,6 +68,18 @@ impl Shape for Sphere {
         self.id
     }
 
+    fn get_tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn get_material(&self) -> Material {
         self.material.clone()
     }
@@ -65,7 +93,7 @@ impl Shape for Sphere {
     }
 
     fn set_transform(&mut self, transform: Matrix<4>) {
-        self.transform = transform;
+        self.transform = transform.to_invertible();
     }
 
     fn intersection(&self, t: f64) -> Intersection {
@@ -93,6 +121,14 @@ impl Shape for Sphere {
     fn local_normal_at(&self, local_point: Tuple) -> Tuple {
         local_point - Tuple::point(0., 0., 0.)
     }
+
+    fn local_uv_at(&self, local_point: Tuple) -> Option<(f64, f64)> {
+        Some(UvMapping::Spherical.uv_at(local_point))
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+    }
 }
 
 #[cfg(test)]
@@ -105,6 +141,7 @@ mod tests {
         ray::Ray,
         shapes::{sphere::Sphere, Shape},
         tuple::Tuple,
+        utils::fuzzy_equal::fuzzy_equal,
     };
 
     #[test]
@@ -270,6 +307,70 @@ mod tests {
         assert_eq!(n, Tuple::vector(0., 0.97014, -0.24254));
     }
 
+    #[test]
+    fn normal_to_world_matches_normal_at_on_a_scaled_sphere() {
+        let s = Sphere::default().set_transform(Matrix::identity().scaling(1., 0.5, 1.));
+        let world_point = Tuple::point(0., 0.5, 0.);
+        let local_point = s.get_transform().inverse() * world_point;
+
+        assert_eq!(
+            s.normal_to_world(s.local_normal_at(local_point)),
+            s.normal_at(world_point)
+        );
+    }
+
+    #[test]
+    fn a_zero_scale_flattens_the_sphere_instead_of_panicking() {
+        let s = Sphere::default().with_transform(Matrix::identity().scaling(0., 1., 1.));
+        let r = Ray::new(Tuple::point(-5., 0., 0.), Tuple::vector(1., 0., 0.));
+
+        assert!(s.transform.is_invertible());
+
+        let xs = s.intersect(&r).unwrap();
+
+        assert_eq!(xs.len(), 2);
+        assert!(fuzzy_equal(xs[0].t, 5.));
+        assert_eq!(s.normal_at(r.position(xs[0].t)), Tuple::vector(-1., 0., 0.));
+    }
+
+    #[test]
+    fn the_normal_on_a_mirrored_sphere_points_outward() {
+        let s = Sphere::default().set_transform(Matrix::identity().scaling(-1., 1., 1.));
+
+        assert_eq!(
+            s.normal_at(Tuple::point(1., 0., 0.)),
+            Tuple::vector(1., 0., 0.)
+        );
+        assert_eq!(
+            s.normal_at(Tuple::point(0., 0., -1.)),
+            Tuple::vector(0., 0., -1.)
+        );
+    }
+
+    #[test]
+    fn the_normal_on_a_flipped_sphere_points_inward() {
+        let s = Sphere::default().set_material(Material::default().set_flip_normals(true));
+
+        let n = s.normal_at(Tuple::point(0., 0., -1.));
+
+        assert_eq!(n, Tuple::vector(0., 0., 1.));
+    }
+
+    #[test]
+    fn the_fluent_builders_match_the_setters() {
+        let s = Sphere::default();
+        let transform = Matrix::identity().scaling(2., 2., 2.);
+        let material = Material::default().set_ambient(1.);
+
+        let fluent = s
+            .clone()
+            .with_transform(transform)
+            .with_material(material.clone());
+        let chained = s.clone().set_transform(transform).set_material(material);
+
+        assert_eq!(fluent, chained);
+    }
+
     #[test]
     fn a_sphere_has_a_default_material() {
         let s = Sphere::default();
@@ -295,4 +396,18 @@ mod tests {
         assert_eq!(s.material.get_transparency(), 1.);
         assert_eq!(s.material.get_refractive_index(), 1.5);
     }
+
+    #[test]
+    fn querying_a_shapes_bounding_box_in_its_parents_space() {
+        let s = Sphere::default().set_transform(
+            Matrix::identity()
+                .scaling(0.5, 2., 4.)
+                .translation(1., -3., 5.),
+        );
+
+        let b = s.parent_space_bounds();
+
+        assert_eq!(b.min, Tuple::point(0.5, -5., 1.));
+        assert_eq!(b.max, Tuple::point(1.5, -1., 9.));
+    }
 }
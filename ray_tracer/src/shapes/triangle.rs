@@ -0,0 +1,233 @@
+use std::{any::Any, rc::Rc};
+
+use uuid::Uuid;
+
+use crate::{
+    bounds::Bounds, constants::EPSILON, intersections::Intersection, material::Material,
+    matrix::Matrix, ray::Ray, tuple::Tuple,
+};
+
+use super::Shape;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triangle {
+    id: Uuid,
+    tag: Option<String>,
+    pub transform: Matrix<4>,
+    pub material: Material,
+    p1: Tuple,
+    p2: Tuple,
+    p3: Tuple,
+    e1: Tuple,
+    e2: Tuple,
+    normal: Tuple,
+}
+
+impl Triangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        let normal = Tuple::cross(&e2, &e1).normalize();
+
+        Self {
+            id: Uuid::new_v4(),
+            tag: None,
+            transform: Matrix::identity(),
+            material: Material::default(),
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal,
+        }
+    }
+
+    /// Get the triangle's first corner.
+    pub fn p1(&self) -> Tuple {
+        self.p1
+    }
+
+    /// Get the triangle's second corner.
+    pub fn p2(&self) -> Tuple {
+        self.p2
+    }
+
+    /// Get the triangle's third corner.
+    pub fn p3(&self) -> Tuple {
+        self.p3
+    }
+
+    pub fn set_material(&mut self, material: Material) -> Self {
+        self.material = material;
+        self.clone()
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform.to_invertible();
+        self.clone()
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Matrix<4>) -> Self {
+        self.transform = transform.to_invertible();
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+}
+
+impl Shape for Triangle {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn get_tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_material(&self) -> Material {
+        self.material.clone()
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    fn get_transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix<4>) {
+        self.transform = transform.to_invertible();
+    }
+
+    fn intersection(&self, t: f64) -> Intersection {
+        Intersection::new(t, Rc::new(self.clone()))
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Option<Vec<Intersection>> {
+        let dir_cross_e2 = Tuple::cross(&ray.direction, &self.e2);
+        let det = Tuple::dot(&self.e1, &dir_cross_e2);
+
+        if det.abs() < EPSILON {
+            return None;
+        }
+
+        let f = 1. / det;
+        let p1_to_origin = ray.origin - self.p1;
+        let u = f * Tuple::dot(&p1_to_origin, &dir_cross_e2);
+
+        if !(0. ..=1.).contains(&u) {
+            return None;
+        }
+
+        let origin_cross_e1 = Tuple::cross(&p1_to_origin, &self.e1);
+        let v = f * Tuple::dot(&ray.direction, &origin_cross_e1);
+
+        if v < 0. || u + v > 1. {
+            return None;
+        }
+
+        let t = f * Tuple::dot(&self.e2, &origin_cross_e1);
+
+        Some(vec![self.intersection(t)])
+    }
+
+    fn local_normal_at(&self, _: Tuple) -> Tuple {
+        self.normal
+    }
+
+    fn bounds(&self) -> Bounds {
+        let corners = [self.p1, self.p2, self.p3];
+        let min = |f: fn(&Tuple) -> f64| corners.iter().map(f).fold(f64::INFINITY, f64::min);
+        let max = |f: fn(&Tuple) -> f64| corners.iter().map(f).fold(f64::NEG_INFINITY, f64::max);
+
+        Bounds::new(
+            Tuple::point(min(|p| p.x), min(|p| p.y), min(|p| p.z)),
+            Tuple::point(max(|p| p.x), max(|p| p.y), max(|p| p.z)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ray::Ray,
+        shapes::{triangle::Triangle, Shape},
+        tuple::Tuple,
+    };
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Tuple::point(0., 1., 0.),
+            Tuple::point(-1., 0., 0.),
+            Tuple::point(1., 0., 0.),
+        )
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let t = triangle();
+
+        assert_eq!(t.e1, Tuple::vector(-1., -1., 0.));
+        assert_eq!(t.e2, Tuple::vector(1., -1., 0.));
+        assert_eq!(t.normal, Tuple::vector(0., 0., -1.));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_triangle() {
+        let t = triangle();
+
+        assert_eq!(t.local_normal_at(Tuple::point(0., 0.5, 0.)), t.normal);
+        assert_eq!(t.local_normal_at(Tuple::point(-0.5, 0.75, 0.)), t.normal);
+        assert_eq!(t.local_normal_at(Tuple::point(0.5, 0.25, 0.)), t.normal);
+    }
+
+    #[test]
+    fn intersecting_a_ray_parallel_to_the_triangle() {
+        let r = Ray::new(Tuple::point(0., -1., -2.), Tuple::vector(0., 1., 0.));
+
+        assert!(triangle().local_intersect(&r).is_none());
+    }
+
+    #[test]
+    fn a_ray_misses_each_edge_of_the_triangle() {
+        let t = triangle();
+        let origins = [
+            Tuple::point(1., 1., -2.),
+            Tuple::point(-1., 1., -2.),
+            Tuple::point(0., -1., -2.),
+        ];
+
+        for origin in origins.iter() {
+            let r = Ray::new(*origin, Tuple::vector(0., 0., 1.));
+
+            assert!(t.local_intersect(&r).is_none());
+        }
+    }
+
+    #[test]
+    fn a_ray_strikes_a_triangle() {
+        let r = Ray::new(Tuple::point(0., 0.5, -2.), Tuple::vector(0., 0., 1.));
+        let xs = triangle().local_intersect(&r).unwrap();
+
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 2.);
+    }
+}
@@ -1,6 +1,25 @@
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use core::{
+    fmt,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use crate::{constants::EPSILON, math, utils::fuzzy_equal::fuzzy_equal};
+
+/// Returned when a slice doesn't hold exactly the three coordinates of a
+/// point or vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrongLength {
+    pub len: usize,
+}
 
-use crate::utils::fuzzy_equal::fuzzy_equal;
+impl fmt::Display for WrongLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected 3 coordinates but got {}", self.len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WrongLength {}
 
 #[derive(Debug, Clone, Copy)]
 pub struct Tuple {
@@ -23,6 +42,21 @@ impl Tuple {
         Self { x, y, z, w: 0.0 }
     }
 
+    /// A point from `[x, y, z]`.
+    pub fn point_from_slice(coordinates: &[f64]) -> Result<Self, WrongLength> {
+        match *coordinates {
+            [x, y, z] => Ok(Self::point(x, y, z)),
+            _ => Err(WrongLength {
+                len: coordinates.len(),
+            }),
+        }
+    }
+
+    /// A vector from `[x, y, z]`.
+    pub fn vector_from_slice(coordinates: &[f64]) -> Result<Self, WrongLength> {
+        Self::point_from_slice(coordinates).map(|point| Self { w: 0.0, ..point })
+    }
+
     pub fn is_point(&self) -> bool {
         self.w == 1.0
     }
@@ -32,7 +66,12 @@ impl Tuple {
     }
 
     pub fn magnitude(&self) -> f64 {
-        (self.x.powf(2.0) + self.y.powf(2.0) + self.z.powf(2.0) + self.w.powf(2.0)).sqrt()
+        math::sqrt(self.magnitude_squared())
+    }
+
+    /// The squared magnitude, cheaper than `magnitude` when only comparing lengths.
+    pub fn magnitude_squared(&self) -> f64 {
+        self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w
     }
 
     pub fn normalize(&self) -> Self {
@@ -46,6 +85,16 @@ impl Tuple {
         }
     }
 
+    /// Like `normalize`, but returns `None` for a (near) zero-length tuple
+    /// instead of producing `NaN` components.
+    pub fn try_normalize(&self) -> Option<Self> {
+        if self.magnitude() < EPSILON {
+            None
+        } else {
+            Some(self.normalize())
+        }
+    }
+
     pub fn dot(a: &Tuple, b: &Tuple) -> f64 {
         (a.x * b.x) + (a.y * b.y) + (a.z * b.z) + (a.w * b.w)
     }
@@ -61,6 +110,25 @@ impl Tuple {
     pub fn reflect(self, normal: Tuple) -> Self {
         self - normal * 2.0_f64 * Self::dot(&self, &normal)
     }
+
+    /// Linearly interpolate between this tuple and `other`, where `t = 0`
+    /// gives `self` and `t = 1` gives `other`.
+    pub fn lerp(&self, other: Tuple, t: f64) -> Self {
+        *self + (other - *self) * t
+    }
+}
+
+/// `[x, y, z]` as a point.
+impl From<[f64; 3]> for Tuple {
+    fn from([x, y, z]: [f64; 3]) -> Self {
+        Self::point(x, y, z)
+    }
+}
+
+impl From<Tuple> for [f64; 4] {
+    fn from(tuple: Tuple) -> Self {
+        [tuple.x, tuple.y, tuple.z, tuple.w]
+    }
 }
 
 impl PartialEq for Tuple {
@@ -139,7 +207,9 @@ impl Div<f64> for Tuple {
 
 #[cfg(test)]
 mod tests {
-    use super::Tuple;
+    use crate::utils::fuzzy_equal::fuzzy_equal;
+
+    use super::{Tuple, WrongLength};
 
     #[test]
     fn tuple_with_w_1_0_is_point() {
@@ -259,6 +329,22 @@ mod tests {
         assert_eq!(v.magnitude(), 14.0_f64.sqrt());
     }
 
+    #[test]
+    fn magnitude_squared_is_the_square_of_the_magnitude() {
+        let v = Tuple::vector(-1.5, 2.25, 3.0);
+
+        assert!(fuzzy_equal(v.magnitude_squared(), v.magnitude().powi(2)));
+    }
+
+    #[test]
+    fn try_normalize_rejects_the_zero_vector() {
+        assert_eq!(Tuple::vector(0., 0., 0.).try_normalize(), None);
+        assert_eq!(
+            Tuple::vector(4., 0., 0.).try_normalize(),
+            Some(Tuple::vector(1., 0., 0.))
+        );
+    }
+
     #[test]
     fn normalizing_vector_4_0_0_gives_1_0_0() {
         let v = Tuple::vector(4.0, 0.0, 0.0);
@@ -298,6 +384,16 @@ mod tests {
         assert_eq!(Tuple::cross(&b, &a), Tuple::vector(1.0, -2.0, 1.0));
     }
 
+    #[test]
+    fn lerp_at_the_halfway_point_gives_the_midpoint() {
+        let a = Tuple::point(1., 2., 3.);
+        let b = Tuple::point(3., -2., 5.);
+
+        assert_eq!(a.lerp(b, 0.), a);
+        assert_eq!(a.lerp(b, 0.5), Tuple::point(2., 0., 4.));
+        assert_eq!(a.lerp(b, 1.), b);
+    }
+
     #[test]
     fn reflecting_a_vector_approaching_at_45_degrees() {
         let v = Tuple::vector(1., -1., 0.);
@@ -317,4 +413,25 @@ mod tests {
 
         assert_eq!(r, Tuple::vector(1., 0., 0.));
     }
+
+    #[test]
+    fn building_tuples_from_slices_and_arrays() {
+        assert_eq!(
+            Tuple::point_from_slice(&[1., 2., 3.]),
+            Ok(Tuple::point(1., 2., 3.))
+        );
+        assert_eq!(
+            Tuple::vector_from_slice(&[1., 2., 3.]),
+            Ok(Tuple::vector(1., 2., 3.))
+        );
+        assert_eq!(
+            Tuple::point_from_slice(&[1., 2.]),
+            Err(WrongLength { len: 2 })
+        );
+        assert_eq!(Tuple::from([1., 2., 3.]), Tuple::point(1., 2., 3.));
+        assert_eq!(
+            <[f64; 4]>::from(Tuple::vector(1., 2., 3.)),
+            [1., 2., 3., 0.]
+        );
+    }
 }
@@ -1 +1,2 @@
 pub mod fuzzy_equal;
+pub mod ordering;
@@ -0,0 +1,57 @@
+use core::cmp::Ordering;
+
+/// Compare two floats with a total order, so that a stray `NaN` sorts last
+/// instead of making `partial_cmp(..).unwrap()` panic mid-render.
+pub fn cmp_f64(a: &f64, b: &f64) -> Ordering {
+    canonical_nan(*a).total_cmp(&canonical_nan(*b))
+}
+
+/// `total_cmp` puts negative `NaN`s first, so turn every `NaN` positive.
+fn canonical_nan(value: f64) -> f64 {
+    if value.is_nan() {
+        f64::NAN
+    } else {
+        value
+    }
+}
+
+/// The largest of the values, or negative infinity for an empty slice.
+pub fn max_f64(values: &[f64]) -> f64 {
+    values
+        .iter()
+        .copied()
+        .max_by(cmp_f64)
+        .unwrap_or(f64::NEG_INFINITY)
+}
+
+/// The smallest of the values, or infinity for an empty slice.
+pub fn min_f64(values: &[f64]) -> f64 {
+    values
+        .iter()
+        .copied()
+        .min_by(cmp_f64)
+        .unwrap_or(f64::INFINITY)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::ordering::{cmp_f64, max_f64, min_f64};
+
+    #[test]
+    fn sorting_with_nan_does_not_panic() {
+        let mut values = [3., f64::NAN, -1., -f64::NAN, 2.];
+
+        values.sort_by(cmp_f64);
+
+        assert_eq!(&values[..3], &[-1., 2., 3.]);
+        assert!(values[3].is_nan() && values[4].is_nan());
+    }
+
+    #[test]
+    fn min_and_max_of_a_slice() {
+        assert_eq!(max_f64(&[1., -4., 3.]), 3.);
+        assert_eq!(min_f64(&[1., -4., 3.]), -4.);
+        assert_eq!(max_f64(&[]), f64::NEG_INFINITY);
+        assert_eq!(min_f64(&[]), f64::INFINITY);
+    }
+}
@@ -1,20 +1,160 @@
+use std::cell::Cell;
+use std::iter::FromIterator;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::bounds::Bounds;
 use crate::color::Color;
-use crate::intersections::{ComputedIntersection, Intersections};
+use crate::constants::EPSILON;
+use crate::intersections::{ComputedIntersection, Intersection, Intersections};
 use crate::ray::Ray;
+use crate::sampling::{tangent_frame, uniform_disk, Rng};
 
 use crate::shapes::Shape;
 use crate::tuple::Tuple;
 
 use crate::light::Light;
-
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::patterns::checkers::Checkers;
+use crate::patterns::{Pattern, Patterns};
+use crate::shapes::plane::Plane;
+use crate::shapes::sphere::Sphere;
+
+/// Shadow rays cast toward a light with a radius.
+const SOFT_SHADOW_SAMPLES: usize = 16;
+/// Fixed so soft shadows render the same every time.
+const SOFT_SHADOW_SEED: u64 = 0x5eed;
+
+/// The objects and light of a scene with the settings for tracing it. Clones
+/// share the objects, so a clone with different settings is cheap.
+#[derive(Clone)]
 pub struct World {
     light: Option<Light>,
-    objects: Vec<Box<dyn Shape>>,
+    objects: Vec<Arc<dyn Shape>>,
+    shadows_enabled: bool,
+    transparent_shadows: bool,
+    environment: Option<Patterns>,
+    sky_gradient: Option<(Color, Color)>,
+    layer: Option<String>,
+    max_reflection_depth: usize,
+    max_refraction_depth: usize,
+    transport_mask: (bool, bool, bool),
+    dedup_coincident: bool,
+    render_bounds: Option<Bounds>,
+    max_intersections: usize,
+}
+
+/// The color seen along a ray together with what it hit, see
+/// `World::shade_ray`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadeResult {
+    pub color: Color,
+    pub hit_object: Option<Uuid>,
+    pub t: Option<f64>,
+}
+
+/// The terms that add up to the color seen along a ray, see
+/// `World::shade_terms`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ShadeTerms {
+    pub ambient: Color,
+    pub diffuse: Color,
+    pub specular: Color,
+    /// Ambient light from the world's environment.
+    pub environment: Color,
+    pub reflection: Color,
+    pub refraction: Color,
+    /// The sky seen by rays that miss every object.
+    pub background: Color,
+}
+
+impl ShadeTerms {
+    /// The color the terms add up to.
+    pub fn total(&self) -> Color {
+        [
+            &self.ambient,
+            &self.diffuse,
+            &self.specular,
+            &self.environment,
+            &self.reflection,
+            &self.refraction,
+            &self.background,
+        ]
+        .iter()
+        // most surfaces neither reflect nor refract, skip adding black
+        .filter(|term| !term.is_black())
+        .fold(Color::new_black(), |acc, term| acc + (*term).clone())
+    }
+}
+
+/// How many more bounces a ray may take, overall and per kind.
+#[derive(Debug, Clone)]
+struct Budget {
+    remaining: usize,
+    reflections: usize,
+    refractions: usize,
+    /// Intersections left to shade, shared by every ray spawned from the
+    /// same primary ray.
+    intersections: Rc<Cell<usize>>,
+}
+
+impl Budget {
+    fn reflect(&self) -> Self {
+        Self {
+            remaining: self.remaining - 1,
+            reflections: self.reflections - 1,
+            ..self.clone()
+        }
+    }
+
+    fn refract(&self) -> Self {
+        Self {
+            remaining: self.remaining - 1,
+            refractions: self.refractions - 1,
+            ..self.clone()
+        }
+    }
+
+    /// Take `count` intersections from the shared allowance, returning
+    /// `false` once it is used up.
+    fn spend(&self, count: usize) -> bool {
+        let left = self.intersections.get();
+
+        self.intersections.set(left.saturating_sub(count));
+
+        count <= left
+    }
 }
 
 impl World {
     pub fn new(light: Option<Light>, objects: Vec<Box<dyn Shape>>) -> Self {
-        Self { light, objects }
+        Self {
+            light,
+            objects: objects.into_iter().map(Arc::from).collect(),
+            shadows_enabled: true,
+            transparent_shadows: false,
+            environment: None,
+            sky_gradient: None,
+            layer: None,
+            max_reflection_depth: usize::MAX,
+            max_refraction_depth: usize::MAX,
+            transport_mask: (true, true, true),
+            dedup_coincident: false,
+            render_bounds: None,
+            max_intersections: usize::MAX,
+        }
+    }
+
+    /// Create a world from any collection of shapes, e.g. shapes generated
+    /// by mapping over a range.
+    pub fn from_shapes<I>(light: Option<Light>, shapes: I) -> Self
+    where
+        I: IntoIterator<Item = Box<dyn Shape>>,
+    {
+        Self::new(light, shapes.into_iter().collect())
     }
 
     /// Get a reference to the world's light.
@@ -22,8 +162,13 @@ impl World {
         self.light.as_ref()
     }
 
+    /// Get the world's lights. A world currently holds at most one light.
+    pub fn lights(&self) -> &[Light] {
+        self.light.as_slice()
+    }
+
     /// Get a reference to the world's objects.
-    pub fn objects(&self) -> &[Box<dyn Shape>] {
+    pub fn objects(&self) -> &[Arc<dyn Shape>] {
         self.objects.as_ref()
     }
 
@@ -33,104 +178,644 @@ impl World {
         self
     }
 
-    pub fn intersect_world(&self, ray: &Ray) -> Intersections {
-        let xs = self.objects.iter().fold(vec![], |mut acc, object| {
-            if let Some(intersection) = object.intersect(ray) {
-                acc.extend(intersection);
+    /// The two-sphere world used throughout the book: a light at
+    /// (-10, 10, -10), a unit sphere and a half-sized sphere inside it.
+    pub fn demo() -> Self {
+        let light = Light::new(Tuple::point(-10., 10., -10.), Color::new(1., 1., 1.));
+        let s1 = Sphere::default().with_material(
+            Material::default()
+                .set_color(Color::new(0.8, 1.0, 0.6))
+                .set_diffuse(0.7)
+                .set_specular(0.2),
+        );
+        let s2 = Sphere::default().with_transform(Matrix::identity().scaling(0.5, 0.5, 0.5));
+
+        World::new(Some(light), vec![Box::new(s1), Box::new(s2)])
+    }
+
+    /// Add an infinite checkered floor through the origin.
+    pub fn with_checker_floor(mut self, a: Color, b: Color) -> Self {
+        let floor = Plane::default()
+            .with_material(Material::default().set_pattern(Checkers::new(a, b).into()));
+
+        self.objects.push(Arc::new(floor));
+
+        self
+    }
+
+    /// Get whether the world traces shadow rays.
+    pub fn shadows_enabled(&self) -> bool {
+        self.shadows_enabled
+    }
+
+    /// Enable or disable shadows. When disabled every point is treated as lit
+    /// and no shadow rays are traced, which is handy for quick previews.
+    pub fn set_shadows_enabled(mut self, shadows_enabled: bool) -> Self {
+        self.shadows_enabled = shadows_enabled;
+
+        self
+    }
+
+    /// Get whether transparent objects let part of the light through.
+    pub fn transparent_shadows(&self) -> bool {
+        self.transparent_shadows
+    }
+
+    /// Let transparent objects cast lighter shadows, see `light_intensity_at`.
+    /// Disabled by default, so every blocker shadows completely.
+    pub fn set_transparent_shadows(mut self, transparent_shadows: bool) -> Self {
+        self.transparent_shadows = transparent_shadows;
+
+        self
+    }
+
+    /// Get a reference to the world's environment.
+    pub fn environment(&self) -> Option<&Patterns> {
+        self.environment.as_ref()
+    }
+
+    /// Surround the world with an environment. The pattern is sampled at unit
+    /// direction vectors (as points on the unit sphere) and adds an ambient
+    /// tint to surfaces depending on which way they face.
+    pub fn set_environment(mut self, environment: Patterns) -> Self {
+        self.environment = Some(environment);
+
+        self
+    }
+
+    /// Sample the environment in the given direction.
+    pub fn environment_at(&self, direction: Tuple) -> Option<Color> {
+        let environment = self.environment.as_ref()?;
+        let point = Tuple::point(direction.x, direction.y, direction.z);
+
+        Some(environment.pattern_at(environment.get_transform().inverse() * point))
+    }
+
+    /// Get the sky's horizon and zenith colors.
+    pub fn sky_gradient(&self) -> Option<&(Color, Color)> {
+        self.sky_gradient.as_ref()
+    }
+
+    /// Show a sky behind the world: rays that miss every object see a blend
+    /// from the horizon color, looking level or down, to the zenith color,
+    /// looking straight up. Without a sky misses are black.
+    pub fn set_sky_gradient(mut self, horizon: Color, zenith: Color) -> Self {
+        self.sky_gradient = Some((horizon, zenith));
+
+        self
+    }
+
+    fn sky_at(&self, direction: Tuple) -> Color {
+        match &self.sky_gradient {
+            Some((horizon, zenith)) => {
+                let t = direction.normalize().y.max(0.);
+
+                horizon.clone() * (1. - t) + zenith.clone() * t
             }
-            acc
-        });
+            None => Color::new_black(),
+        }
+    }
 
-        Intersections::new(xs)
+    /// Get the number of mirror bounces a ray may take.
+    pub fn max_reflection_depth(&self) -> usize {
+        self.max_reflection_depth
+    }
+
+    /// Limit the number of mirror bounces along a ray, independently of the
+    /// overall depth passed to `color_at`. Unlimited by default.
+    pub fn set_max_reflection_depth(mut self, max_reflection_depth: usize) -> Self {
+        self.max_reflection_depth = max_reflection_depth;
+
+        self
+    }
+
+    /// Get the number of refractions a ray may take.
+    pub fn max_refraction_depth(&self) -> usize {
+        self.max_refraction_depth
+    }
+
+    /// Limit the number of refractions along a ray, independently of the
+    /// overall depth passed to `color_at`. Unlimited by default.
+    pub fn set_max_refraction_depth(mut self, max_refraction_depth: usize) -> Self {
+        self.max_refraction_depth = max_refraction_depth;
+
+        self
+    }
+
+    /// Get which light transport terms are shaded, as
+    /// `(reflect, refract, direct)`.
+    pub fn transport_mask(&self) -> (bool, bool, bool) {
+        self.transport_mask
+    }
+
+    /// Choose which terms `shade_hit` includes, to isolate them when
+    /// debugging: reflections, refractions and the direct surface lighting
+    /// (ambient included). All enabled by default.
+    pub fn set_transport_mask(mut self, reflect: bool, refract: bool, direct: bool) -> Self {
+        self.transport_mask = (reflect, refract, direct);
+
+        self
+    }
+
+    /// Get whether coincident hits on different objects are merged.
+    pub fn dedup_coincident(&self) -> bool {
+        self.dedup_coincident
+    }
+
+    /// Merge hits on different objects at the same distance, see
+    /// `Intersections::dedup_coincident`, so coplanar surfaces shade as the
+    /// one added to the world first instead of z-fighting. Off by default.
+    pub fn set_dedup_coincident(mut self, dedup_coincident: bool) -> Self {
+        self.dedup_coincident = dedup_coincident;
+
+        self
+    }
+
+    /// Get the box outside of which geometry is ignored.
+    pub fn render_bounds(&self) -> Option<&Bounds> {
+        self.render_bounds.as_ref()
+    }
+
+    /// Ignore hits outside of `render_bounds`, shadow rays included, e.g. to
+    /// render a section through a building. `None` renders everything.
+    pub fn set_render_bounds(mut self, render_bounds: Option<Bounds>) -> Self {
+        self.render_bounds = render_bounds;
+
+        self
+    }
+
+    /// Check whether the hit at `t` along the ray lies inside the render
+    /// bounds.
+    fn in_render_bounds(&self, ray: &Ray, t: f64) -> bool {
+        match &self.render_bounds {
+            Some(bounds) => bounds.contains_point(ray.position(t)),
+            None => true,
+        }
+    }
+
+    /// Get how many intersections a ray and its bounces may shade.
+    pub fn max_intersections(&self) -> usize {
+        self.max_intersections
+    }
+
+    /// Cap the intersections a primary ray and all the rays it spawns may
+    /// shade together. Past the cap rays see the background, which bounds
+    /// the work on scenes such as many nested glass shells. Unlimited by
+    /// default.
+    pub fn set_max_intersections(mut self, max_intersections: usize) -> Self {
+        self.max_intersections = max_intersections;
+
+        self
+    }
+
+    fn budget(&self, remaining: usize) -> Budget {
+        Budget {
+            remaining,
+            reflections: self.max_reflection_depth,
+            refractions: self.max_refraction_depth,
+            intersections: Rc::new(Cell::new(self.max_intersections)),
+        }
+    }
+
+    /// Get the tag of the objects the world is restricted to.
+    pub fn layer(&self) -> Option<&str> {
+        self.layer.as_deref()
+    }
+
+    /// Restrict the world to the objects tagged `layer`. Other objects are
+    /// skipped by every ray, shadow rays included. `None` shows everything.
+    pub fn set_layer(mut self, layer: Option<String>) -> Self {
+        self.layer = layer;
+
+        self
+    }
+
+    /// The objects on the current layer.
+    fn visible_objects(&self) -> impl Iterator<Item = &Arc<dyn Shape>> {
+        self.objects.iter().filter(move |object| match &self.layer {
+            Some(layer) => object.get_tag() == Some(layer.as_str()),
+            None => true,
+        })
+    }
+
+    /// Intersect every visible object with the ray. A ray without a
+    /// direction hits nothing.
+    pub fn intersect_world(&self, ray: &Ray) -> Intersections {
+        if ray.direction.magnitude() < EPSILON {
+            return Intersections::default();
+        }
+
+        let mut xs = Intersections::default();
+        xs.extend(
+            self.visible_objects()
+                .filter_map(|object| object.intersect(ray))
+                .flatten()
+                .filter(|i| self.in_render_bounds(ray, i.t))
+                .collect(),
+        );
+
+        if self.dedup_coincident {
+            xs.dedup_coincident()
+        } else {
+            xs
+        }
+    }
+
+    /// The world-space box around all objects with finite bounds. Infinite
+    /// objects such as planes are left out so the box can frame the scene.
+    pub fn bounds(&self) -> Bounds {
+        self.objects
+            .iter()
+            .map(|object| object.parent_space_bounds())
+            .filter(Bounds::is_finite)
+            .fold(Bounds::empty(), |acc, bounds| acc.merge(&bounds))
+    }
+
+    /// Find the object under the ray without shading it, e.g. for selecting
+    /// objects in an editor. Returns the hit object's id and distance.
+    pub fn pick(&self, ray: &Ray) -> Option<(Uuid, f64)> {
+        self.intersect_world(ray)
+            .hit()
+            .map(|hit| (hit.object.id(), hit.t))
     }
 
     // TODO: add support multiple light sources
     pub fn shade_hit(&self, comps: ComputedIntersection, remaining: usize) -> Color {
-        let is_shadowed = self.is_shadowed(comps.over_point);
-        let material = comps.object.get_material();
-        let surface_color = comps.object.get_material().lighting(
-            comps.object.clone(),
-            self.light.as_ref().unwrap(),
-            comps.over_point,
-            comps.eyev,
-            comps.normalv,
-            is_shadowed,
-        );
-        let reflected_color = self.reflected_color(&comps, remaining);
-        let refracted_color = self.refracted_color(&comps, remaining);
-
-        if material.get_reflective() > 0. && material.get_transparency() > 0. {
+        self.shade_hit_as(comps, self.budget(remaining), false)
+    }
+
+    /// Shade a hit. Surfaces seen by reflected or refracted (secondary) rays
+    /// use their material's reflection material when one is set.
+    fn shade_hit_as(&self, comps: ComputedIntersection, budget: Budget, secondary: bool) -> Color {
+        self.shade_hit_terms(comps, budget, secondary).total()
+    }
+
+    fn shade_hit_terms(
+        &self,
+        comps: ComputedIntersection,
+        budget: Budget,
+        secondary: bool,
+    ) -> ShadeTerms {
+        let mut material = comps.object.get_material();
+
+        if secondary {
+            if let Some(reflection_material) = material.get_reflection_material() {
+                material = reflection_material.clone();
+            }
+        }
+
+        // a two-sided surface lit from behind is shadowed from its far side,
+        // or it would block its own light
+        let lightv = self.light.as_ref().unwrap().position - comps.over_point;
+        let shadow_point = if material.get_two_sided() && Tuple::dot(&lightv, &comps.normalv) < 0. {
+            comps.under_point
+        } else {
+            comps.over_point
+        };
+        let light_intensity = self.light_intensity_at(shadow_point);
+
+        let (reflect, refract, direct) = self.transport_mask;
+        let mut terms = ShadeTerms::default();
+
+        if direct {
+            let (ambient, diffuse, specular) = material.lighting_terms(
+                comps.object.clone(),
+                self.light.as_ref().unwrap(),
+                comps.over_point,
+                comps.eyev,
+                comps.normalv,
+                light_intensity,
+            );
+
+            terms.ambient = ambient;
+            terms.diffuse = diffuse;
+            terms.specular = specular;
+
+            if let Some(environment) = self.environment_at(comps.normalv) {
+                terms.environment = material.environment_ambient(
+                    comps.object.clone(),
+                    comps.over_point,
+                    environment,
+                );
+            }
+        }
+
+        let transparency = if refract {
+            material.get_transparency()
+        } else {
+            0.
+        };
+        let refracted_color = self.refracted_color_with(&comps, &budget, transparency);
+        let is_dielectric = material.get_dielectric() && material.get_transparency() > 0.;
+        // the Fresnel term alone decides how much a dielectric reflects
+        let reflective = match (reflect, is_dielectric) {
+            (false, _) => 0.,
+            (true, true) => 1.,
+            (true, false) => material.get_reflective(),
+        };
+        let reflected_color =
+            self.reflected_color_with(&comps, &budget, reflective, material.get_reflective_color());
+
+        if is_dielectric || (material.get_reflective() > 0. && material.get_transparency() > 0.) {
             let reflectance = comps.schlick();
 
-            surface_color + reflected_color * reflectance + refracted_color * (1. - reflectance)
+            terms.reflection = reflected_color * reflectance;
+            terms.refraction = refracted_color * (1. - reflectance);
         } else {
-            surface_color + reflected_color + refracted_color
+            terms.reflection = reflected_color;
+            terms.refraction = refracted_color;
         }
+
+        terms
     }
 
     pub fn color_at(&self, ray: &Ray, remaining: usize) -> Color {
+        self.shade_ray(ray, remaining).color
+    }
+
+    /// Shade a ray like `color_at`, also reporting the id of the object hit
+    /// and the distance to it.
+    pub fn shade_ray(&self, ray: &Ray, remaining: usize) -> ShadeResult {
+        self.shade_ray_clipped(ray, remaining, None)
+    }
+
+    /// Like `color_at`, but ignoring intersections on the positive side of a
+    /// clip plane, given as a point on the plane and its normal. Only the
+    /// first ray is clipped, bounces and shadows see the whole world.
+    pub fn color_at_clipped(
+        &self,
+        ray: &Ray,
+        remaining: usize,
+        clip_plane: Option<(Tuple, Tuple)>,
+    ) -> Color {
+        self.shade_ray_clipped(ray, remaining, clip_plane).color
+    }
+
+    /// Like `shade_ray`, with the clip plane of `color_at_clipped`. The hit
+    /// reported is the first one left after clipping.
+    pub fn shade_ray_clipped(
+        &self,
+        ray: &Ray,
+        remaining: usize,
+        clip_plane: Option<(Tuple, Tuple)>,
+    ) -> ShadeResult {
         let xs = self.intersect_world(ray);
+        let xs = match clip_plane {
+            Some((point, normal)) => Intersections::new(
+                xs.data()
+                    .iter()
+                    .filter(|i| Tuple::dot(&(ray.position(i.t) - point), &normal) <= 0.)
+                    .map(|i| Intersection::new(i.t, i.object.clone()))
+                    .collect(),
+            ),
+            None => xs,
+        };
+        let hit = xs.hit();
+
+        ShadeResult {
+            color: self.shade_intersections(ray, &xs, self.budget(remaining), false),
+            hit_object: hit.map(|i| i.object.id()),
+            t: hit.map(|i| i.t),
+        }
+    }
+
+    fn color_at_as(&self, ray: &Ray, budget: Budget, secondary: bool) -> Color {
+        let xs = self.intersect_world(ray);
+
+        self.shade_intersections(ray, &xs, budget, secondary)
+    }
+
+    fn shade_intersections(
+        &self,
+        ray: &Ray,
+        xs: &Intersections,
+        budget: Budget,
+        secondary: bool,
+    ) -> Color {
+        if !budget.spend(xs.len()) {
+            return self.sky_at(ray.direction);
+        }
+
         match xs.hit() {
+            Some(intersection) => {
+                let comps = intersection.prepare_computations(ray, xs);
+                self.shade_hit_as(comps, budget, secondary)
+            }
+            None => self.sky_at(ray.direction),
+        }
+    }
+
+    /// Shade a ray like `color_at`, keeping the terms that make up its color
+    /// apart. Rays that miss only see the background.
+    pub fn shade_terms(&self, ray: &Ray, remaining: usize) -> ShadeTerms {
+        let xs = self.intersect_world(ray);
+        let budget = self.budget(remaining);
+
+        match xs.hit().filter(|_| budget.spend(xs.len())) {
             Some(intersection) => {
                 let comps = intersection.prepare_computations(ray, &xs);
-                self.shade_hit(comps, remaining)
+                self.shade_hit_terms(comps, budget, false)
             }
-            None => Color::new_black(),
+            None => ShadeTerms {
+                background: self.sky_at(ray.direction),
+                ..ShadeTerms::default()
+            },
         }
     }
 
     pub fn is_shadowed(&self, point: Tuple) -> bool {
-        let v = self.light.as_ref().unwrap().position - point;
+        if !self.shadows_enabled {
+            return false;
+        }
+
+        !self.visible(point, self.light.as_ref().unwrap().position)
+    }
+
+    /// Check whether the segment between two points is free of
+    /// shadow-casting objects.
+    pub fn visible(&self, a: Tuple, b: Tuple) -> bool {
+        let v = b - a;
         let distance = v.magnitude();
-        let direction = v.normalize();
+        let r = Ray::new(a, v.normalize());
+
+        !self.any_hit_before(&r, distance)
+    }
+
+    /// The fraction of the light that reaches the point. Opaque blockers shadow
+    /// the point completely. With transparent shadows enabled, each transparent
+    /// blocker only occludes `1 - transparency` of the remaining light, a cheap
+    /// stand-in for light refracted through glass.
+    ///
+    /// A light with a radius is sampled at several points across its disk as
+    /// seen from `point`, and the results are averaged into a soft shadow.
+    pub fn light_intensity_at(&self, point: Tuple) -> f64 {
+        if !self.shadows_enabled {
+            return 1.;
+        }
+
+        let light = self.light.as_ref().unwrap();
+
+        if light.radius <= 0. {
+            return self.light_intensity_from(point, light.position);
+        }
+
+        let (tangent, bitangent) = tangent_frame((light.position - point).normalize());
+        let mut rng = Rng::new(SOFT_SHADOW_SEED);
 
-        let r = Ray::new(point, direction);
-        let intersections = self.intersect_world(&r);
+        let total: f64 = (0..SOFT_SHADOW_SAMPLES)
+            .map(|_| {
+                let (x, y) = uniform_disk(&mut rng);
+                let target =
+                    light.position + tangent * (x * light.radius) + bitangent * (y * light.radius);
 
-        let h = intersections.hit();
+                self.light_intensity_from(point, target)
+            })
+            .sum();
+
+        total / SOFT_SHADOW_SAMPLES as f64
+    }
 
-        if let Some(intersection) = h {
-            if intersection.t < distance {
-                return true;
+    /// The fraction of light leaving `target` that reaches `point`.
+    fn light_intensity_from(&self, point: Tuple, target: Tuple) -> f64 {
+        if !self.transparent_shadows {
+            return if self.visible(point, target) { 1. } else { 0. };
+        }
+
+        let v = target - point;
+        let distance = v.magnitude();
+        let r = Ray::new(point, v.normalize());
+
+        let mut intensity = 1.;
+
+        for object in self.visible_objects() {
+            let material = object.get_material();
+
+            if !material.casts_shadow() {
+                continue;
+            }
+
+            let blocks = object.intersect(&r).is_some_and(|xs| {
+                xs.iter()
+                    .any(|i| i.t > 0. && i.t < distance && self.in_render_bounds(&r, i.t))
+            });
+
+            if blocks {
+                intensity *= material.get_transparency();
+
+                if intensity <= 0. {
+                    return 0.;
+                }
             }
         }
 
-        return false;
+        intensity
+    }
+
+    /// Check if any shadow-casting object intersects the ray with
+    /// `0 < t < distance`. Unlike `intersect_world` this stops at the first
+    /// such intersection and does not sort, which is all a shadow ray needs.
+    pub fn any_hit_before(&self, ray: &Ray, distance: f64) -> bool {
+        self.visible_objects().any(|object| {
+            object.get_material().casts_shadow()
+                && object.intersect(ray).is_some_and(|xs| {
+                    xs.iter()
+                        .any(|i| i.t > 0. && i.t < distance && self.in_render_bounds(ray, i.t))
+                })
+        })
     }
 
     pub fn reflected_color(&self, comps: &ComputedIntersection, remaining: usize) -> Color {
-        if remaining <= 0 || comps.object.get_material().get_reflective() == 0. {
+        let material = comps.object.get_material();
+
+        self.reflected_color_with(
+            comps,
+            &self.budget(remaining),
+            material.get_reflective(),
+            material.get_reflective_color(),
+        )
+    }
+
+    fn reflected_color_with(
+        &self,
+        comps: &ComputedIntersection,
+        budget: &Budget,
+        reflective: f64,
+        tint: &Color,
+    ) -> Color {
+        if budget.remaining == 0 || budget.reflections == 0 || reflective == 0. {
             return Color::new_black();
         }
 
         let reflect_ray = Ray::new(comps.over_point, comps.reflectv);
-        let color = self.color_at(&reflect_ray, remaining - 1);
+        let color = self.color_at_as(&reflect_ray, budget.reflect(), true);
 
-        return color * comps.object.get_material().get_reflective();
+        color * tint.clone() * reflective
     }
 
     pub fn refracted_color(&self, comps: &ComputedIntersection, remaining: usize) -> Color {
-        if comps.object.get_material().get_transparency() == 0. || remaining <= 0 {
+        let transparency = comps.object.get_material().get_transparency();
+
+        self.refracted_color_with(comps, &self.budget(remaining), transparency)
+    }
+
+    fn refracted_color_with(
+        &self,
+        comps: &ComputedIntersection,
+        budget: &Budget,
+        transparency: f64,
+    ) -> Color {
+        if transparency == 0. || budget.remaining == 0 || budget.refractions == 0 {
             return Color::new_black();
-        } else {
-            let n_ratio = comps.n1 / comps.n2;
+        }
 
-            let cos_i = Tuple::dot(&comps.eyev, &comps.normalv);
-            let sin2_t = n_ratio.powf(2.) * (1. - cos_i.powf(2.));
+        let dispersion = comps.object.get_material().get_dispersion();
+        let trace = |offset: f64| match Self::refracted_ray(comps, offset) {
+            Some(refract_ray) => self.color_at_as(&refract_ray, budget.refract(), true),
+            None => Color::new_black(),
+        };
 
-            if sin2_t > 1. {
-                return Color::new_black();
-            }
+        let color = if dispersion == 0. {
+            trace(0.)
+        } else {
+            Color::new(
+                trace(-dispersion).red(),
+                trace(0.).green(),
+                trace(dispersion).blue(),
+            )
+        };
+
+        color * transparency
+    }
 
-            let cos_t = (1.0 - sin2_t).sqrt();
-            let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
-            let refract_ray = Ray::new(comps.under_point, direction);
+    /// The ray refracted through the hit, with `offset` added to the
+    /// refractive index of the object being hit. `None` on total internal
+    /// reflection.
+    fn refracted_ray(comps: &ComputedIntersection, offset: f64) -> Option<Ray> {
+        let (n1, n2) = if comps.inside {
+            (comps.n1 + offset, comps.n2)
+        } else {
+            (comps.n1, comps.n2 + offset)
+        };
+        let n_ratio = n1 / n2;
 
-            let color = self.color_at(&refract_ray, remaining - 1)
-                * comps.object.get_material().get_transparency();
+        let cos_i = Tuple::dot(&comps.eyev, &comps.normalv);
+        let sin2_t = n_ratio.powf(2.) * (1. - cos_i.powf(2.));
 
-            return color;
+        if sin2_t > 1. {
+            return None;
         }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
+
+        Some(Ray::new(comps.under_point, direction))
+    }
+}
+
+impl FromIterator<Box<dyn Shape>> for World {
+    /// Collect shapes into a world without a light.
+    fn from_iter<I: IntoIterator<Item = Box<dyn Shape>>>(shapes: I) -> Self {
+        Self::from_shapes(None, shapes)
     }
 }
 
@@ -139,6 +824,17 @@ impl Default for World {
         Self {
             light: None,
             objects: vec![],
+            shadows_enabled: true,
+            transparent_shadows: false,
+            environment: None,
+            sky_gradient: None,
+            layer: None,
+            max_reflection_depth: usize::MAX,
+            max_refraction_depth: usize::MAX,
+            transport_mask: (true, true, true),
+            dedup_coincident: false,
+            render_bounds: None,
+            max_intersections: usize::MAX,
         }
     }
 }
@@ -147,42 +843,54 @@ impl Default for World {
 mod tests {
 
     use std::rc::Rc;
+    use std::sync::Arc;
 
     use crate::{
+        bounds::Bounds,
         color::Color,
+        constants::EPSILON,
         intersections::{Intersection, Intersections},
         light::Light,
         material::Material,
         matrix::Matrix,
-        patterns::test_pattern::TestPattern,
+        patterns::{stripe::Stripe, test_pattern::TestPattern, Patterns},
         ray::Ray,
-        shapes::{plane::Plane, sphere::Sphere, Shape},
+        shapes::{cube::Cube, plane::Plane, sphere::Sphere, Shape},
         tuple::Tuple,
+        utils::fuzzy_equal::fuzzy_equal,
     };
 
     use super::World;
 
-    fn default_world() -> World {
-        let light = Light::new(Tuple::point(-10., 10., -10.), Color::new(1., 1., 1.));
-        let s1 = Sphere::default().set_material(
-            Material::default()
-                .set_color(Color::new(0.8, 1.0, 0.6))
-                .set_diffuse(0.7)
-                .set_specular(0.2),
-        );
-        let s2 = Sphere::default().set_transform(Matrix::identity().scaling(0.5, 0.5, 0.5));
-
-        World::new(Some(light), vec![Box::new(s1), Box::new(s2)])
-    }
-
     #[test]
     fn creating_a_world() {
         let w = World::default();
 
-        assert!(w.objects.is_empty());
+        assert!(w.objects().is_empty());
         assert!(w.light.is_none());
     }
 
+    #[test]
+    fn building_a_world_from_generated_shapes() {
+        let sphere_at = |i: i32| -> Box<dyn Shape> {
+            Box::new(
+                Sphere::default().set_transform(Matrix::identity().translation(i as f64, 0., 0.)),
+            )
+        };
+
+        let w = World::from_shapes(Some(Light::default()), (0..5).map(sphere_at));
+        let collected: World = (0..5).map(sphere_at).collect();
+
+        assert_eq!(w.objects().len(), 5);
+        assert_eq!(w.light(), Some(&Light::default()));
+        assert_eq!(
+            w.objects()[4].get_transform(),
+            Matrix::identity().translation(4., 0., 0.)
+        );
+        assert_eq!(collected.objects().len(), 5);
+        assert_eq!(collected.light(), None);
+    }
+
     #[test]
     fn the_default_world() {
         let light = Light::new(Tuple::point(-10., 10., -10.), Color::new(1., 1., 1.));
@@ -194,15 +902,15 @@ mod tests {
         );
         let _s2 = Sphere::default().set_transform(Matrix::identity().scaling(0.5, 0.5, 0.5));
 
-        let w = default_world();
+        let w = World::demo();
 
-        assert_eq!(w.light.unwrap(), light);
-        assert_eq!(w.objects.len(), 2);
+        assert_eq!(w.light(), Some(&light));
+        assert_eq!(w.objects().len(), 2);
     }
 
     #[test]
     fn intersect_a_world_with_a_ray() {
-        let w = default_world();
+        let w = World::demo();
         let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
         let xs = w.intersect_world(&r);
 
@@ -215,10 +923,10 @@ mod tests {
 
     #[test]
     fn shading_an_intersection() {
-        let w = default_world();
+        let w = World::demo();
         let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
 
-        let shape = &w.objects[0];
+        let shape = &w.objects()[0];
         let i = shape.intersection(4.);
         let comps = i.prepare_computations(&r, &Intersections::default());
 
@@ -229,14 +937,14 @@ mod tests {
 
     #[test]
     fn shading_an_intersection_from_the_inside() {
-        let w = default_world().set_light(Light::new(
+        let w = World::demo().set_light(Light::new(
             Tuple::point(0., 0.25, 0.),
             Color::new(1., 1., 1.),
         ));
 
         let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
 
-        let shape = &w.objects[1];
+        let shape = &w.objects()[1];
         let i = shape.intersection(0.5);
         let comps = i.prepare_computations(&r, &Intersections::default());
 
@@ -247,7 +955,7 @@ mod tests {
 
     #[test]
     fn the_color_when_a_ray_misses() {
-        let w = default_world();
+        let w = World::demo();
         let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 1., 0.));
 
         let c = w.color_at(&r, 5);
@@ -257,7 +965,7 @@ mod tests {
 
     #[test]
     fn the_color_when_a_ray_hits() {
-        let w = default_world();
+        let w = World::demo();
         let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
 
         let c = w.color_at(&r, 5);
@@ -265,6 +973,79 @@ mod tests {
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
     }
 
+    #[test]
+    fn shade_ray_reports_the_object_hit_and_its_distance() {
+        let w = World::demo();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+
+        let result = w.shade_ray(&r, 5);
+
+        assert_eq!(result.color, Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(result.hit_object, Some(w.objects()[0].id()));
+        assert_eq!(result.t, Some(4.));
+    }
+
+    #[test]
+    fn disabling_direct_lighting_leaves_only_reflections_and_refractions() {
+        let w = World::demo().set_transport_mask(true, true, false);
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+
+        assert_eq!(w.transport_mask(), (true, true, false));
+        assert_eq!(w.color_at(&r, 5), Color::new_black());
+    }
+
+    #[test]
+    fn a_ray_without_a_direction_intersects_nothing() {
+        let w = World::demo();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 0.));
+
+        assert!(w.intersect_world(&r).is_empty());
+        assert_eq!(w.color_at(&r, 5), Color::new_black());
+    }
+
+    #[test]
+    fn missed_rays_see_the_sky_gradient() {
+        let horizon = Color::new(1., 1., 1.);
+        let zenith = Color::new(0.2, 0.4, 1.);
+        let w = World::default().set_sky_gradient(horizon.clone(), zenith.clone());
+        let up = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.));
+        let level = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(1., 0., 0.));
+
+        assert_eq!(w.color_at(&up, 5), zenith);
+        assert_eq!(w.color_at(&level, 5), horizon);
+    }
+
+    #[test]
+    fn coincident_planes_shade_as_the_first_one() {
+        let red =
+            Plane::default().with_material(Material::default().set_color(Color::new(1., 0., 0.)));
+        let blue =
+            Plane::default().with_material(Material::default().set_color(Color::new(0., 0., 1.)));
+        let light = Light::new(Tuple::point(0., 10., 0.), Color::new_white());
+        let w = World::new(Some(light), vec![Box::new(red.clone()), Box::new(blue)])
+            .set_dedup_coincident(true);
+        let r = Ray::new(Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.));
+
+        let xs = w.intersect_world(&r);
+        let result = w.shade_ray(&r, 5);
+
+        assert_eq!(xs.len(), 1);
+        assert_eq!(result.hit_object, Some(red.id()));
+        assert_eq!(result.color, Color::new(1.9, 0.9, 0.9));
+    }
+
+    #[test]
+    fn shade_ray_reports_no_hit_for_a_miss() {
+        let w = World::demo();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 1., 0.));
+
+        let result = w.shade_ray(&r, 5);
+
+        assert_eq!(result.color, Color::new_black());
+        assert_eq!(result.hit_object, None);
+        assert_eq!(result.t, None);
+    }
+
     #[test]
     fn the_color_with_an_intersection_behind_the_ray() {
         let light = Light::new(Tuple::point(-10., 10., -10.), Color::new(1., 1., 1.));
@@ -281,7 +1062,7 @@ mod tests {
 
         let w = World::new(Some(light), vec![Box::new(s1), Box::new(s2)]);
 
-        let inner = &w.objects[1];
+        let inner = &w.objects()[1];
 
         let r = Ray::new(Tuple::point(0., 0., 0.75), Tuple::vector(0., 0., -1.));
 
@@ -292,7 +1073,7 @@ mod tests {
 
     #[test]
     fn there_is_no_shadow_when_nothing_is_collinear_with_point_and_light() {
-        let w = default_world();
+        let w = World::demo();
         let p = Tuple::point(0., 10., 0.);
 
         assert_eq!(w.is_shadowed(p), false);
@@ -300,7 +1081,7 @@ mod tests {
 
     #[test]
     fn the_shadow_when_an_object_is_between_the_point_and_the_light() {
-        let w = default_world();
+        let w = World::demo();
         let p = Tuple::point(10., -10., 10.);
 
         assert_eq!(w.is_shadowed(p), true);
@@ -308,7 +1089,7 @@ mod tests {
 
     #[test]
     fn there_is_no_shadow_when_an_object_is_behind_the_light() {
-        let w = default_world();
+        let w = World::demo();
         let p = Tuple::point(-20., 20., -20.);
 
         assert_eq!(w.is_shadowed(p), false);
@@ -316,12 +1097,20 @@ mod tests {
 
     #[test]
     fn there_is_no_shadow_when_an_object_is_behind_the_point() {
-        let w = default_world();
+        let w = World::demo();
         let p = Tuple::point(-2., 2., -2.);
 
         assert_eq!(w.is_shadowed(p), false);
     }
 
+    #[test]
+    fn points_on_opposite_sides_of_a_sphere_are_not_visible() {
+        let w = World::demo();
+
+        assert!(!w.visible(Tuple::point(0., 0., -5.), Tuple::point(0., 0., 5.)));
+        assert!(w.visible(Tuple::point(0., 5., -5.), Tuple::point(0., 5., 5.)));
+    }
+
     #[test]
     fn shade_hit_is_given_an_intersection_in_shadow() {
         let light = Light::new(Tuple::point(0., 0., -10.), Color::new(1., 1., 1.));
@@ -337,6 +1126,23 @@ mod tests {
         assert_eq!(c, Color::new(0.1, 0.1, 0.1));
     }
 
+    #[test]
+    fn a_two_sided_plane_is_lit_by_a_light_behind_it() {
+        let r = Ray::new(Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.));
+        let color_with = |material: Material| {
+            let light = Light::new(Tuple::point(0., -10., 0.), Color::new_white());
+            let plane = Plane::default().with_material(material);
+
+            World::new(Some(light), vec![Box::new(plane)]).color_at(&r, 5)
+        };
+
+        assert_eq!(color_with(Material::default()), Color::new(0.1, 0.1, 0.1));
+        assert_eq!(
+            color_with(Material::default().set_two_sided(true)),
+            Color::new(1., 1., 1.)
+        );
+    }
+
     #[test]
     fn the_reflected_color_for_a_nonreflective_material() {
         let light = Light::new(Tuple::point(-10., 10., -10.), Color::new(1., 1., 1.));
@@ -353,7 +1159,7 @@ mod tests {
 
         let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
 
-        let i = w.objects[1].intersection(1.);
+        let i = w.objects()[1].intersection(1.);
         let comps = i.prepare_computations(&r, &Intersections::default());
         let color = w.reflected_color(&comps, 5);
 
@@ -362,40 +1168,69 @@ mod tests {
 
     #[test]
     fn the_reflected_color_for_a_reflective_material() {
-        let mut w = default_world();
+        let mut w = World::demo();
         let shape = Plane::default()
             .set_material(Material::default().set_reflective(0.5))
             .set_transform(Matrix::identity().translation(0., -1., 0.));
 
-        w.objects.push(Box::new(shape));
+        w.objects.push(Arc::new(shape));
 
         let r = Ray::new(
             Tuple::point(0., 0., -3.),
             Tuple::vector(0., -2.0_f64.sqrt() / 2., 2.0_f64.sqrt() / 2.),
         );
 
-        let i = w.objects[2].intersection(2.0_f64.sqrt());
+        let i = w.objects()[2].intersection(2.0_f64.sqrt());
         let comps = i.prepare_computations(&r, &Intersections::default());
         let color = w.reflected_color(&comps, 5);
 
         assert_eq!(color, Color::new(0.190332, 0.237915, 0.142749));
     }
 
+    #[test]
+    fn a_tinted_reflective_material_colors_its_reflections() {
+        let light = Light::new(Tuple::point(-10., 10., -10.), Color::new_white());
+        let white = Sphere::default().with_material(
+            Material::default()
+                .set_ambient(1.)
+                .set_diffuse(0.)
+                .set_specular(0.),
+        );
+        let gold = Plane::default()
+            .with_material(
+                Material::default()
+                    .set_reflective(1.)
+                    .set_reflective_color(Color::new(1., 0.8, 0.3)),
+            )
+            .with_transform(Matrix::identity().translation(0., -1., 0.));
+        let w = World::new(Some(light), vec![Box::new(white), Box::new(gold)]);
+
+        let r = Ray::new(
+            Tuple::point(0., 0., -3.),
+            Tuple::vector(0., -2.0_f64.sqrt() / 2., 2.0_f64.sqrt() / 2.),
+        );
+        let i = w.objects()[1].intersection(2.0_f64.sqrt());
+        let comps = i.prepare_computations(&r, &Intersections::default());
+        let color = w.reflected_color(&comps, 5);
+
+        assert_eq!(color, Color::new(1., 0.8, 0.3));
+    }
+
     #[test]
     fn shade_hit_with_a_reflective_material() {
-        let mut w = default_world();
+        let mut w = World::demo();
         let shape = Plane::default()
             .set_material(Material::default().set_reflective(0.5))
             .set_transform(Matrix::identity().translation(0., -1., 0.));
 
-        w.objects.push(Box::new(shape));
+        w.objects.push(Arc::new(shape));
 
         let r = Ray::new(
             Tuple::point(0., 0., -3.),
             Tuple::vector(0., -2.0_f64.sqrt() / 2., 2.0_f64.sqrt() / 2.),
         );
 
-        let i = w.objects[2].intersection(2.0_f64.sqrt());
+        let i = w.objects()[2].intersection(2.0_f64.sqrt());
         let comps = i.prepare_computations(&r, &Intersections::default());
         let color = w.shade_hit(comps, 5);
 
@@ -413,8 +1248,8 @@ mod tests {
             .set_material(Material::default().set_reflective(1.))
             .set_transform(Matrix::identity().translation(0., 1., 0.));
 
-        w.objects.push(Box::new(lower));
-        w.objects.push(Box::new(upper));
+        w.objects.push(Arc::new(lower));
+        w.objects.push(Arc::new(upper));
 
         let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.));
 
@@ -423,21 +1258,63 @@ mod tests {
         assert!(true);
     }
 
+    #[test]
+    fn reflection_and_refraction_depths_are_limited_separately() {
+        let mut w = World::demo()
+            .set_max_reflection_depth(0)
+            .set_max_refraction_depth(5);
+        let mirror = Plane::default()
+            .with_material(Material::default().set_reflective(0.5))
+            .with_transform(Matrix::identity().translation(0., -1., 0.));
+        w.objects.push(Arc::new(mirror));
+
+        let r = Ray::new(
+            Tuple::point(0., 0., -3.),
+            Tuple::vector(0., -2.0_f64.sqrt() / 2., 2.0_f64.sqrt() / 2.),
+        );
+        let i = w.objects()[2].intersection(2.0_f64.sqrt());
+        let comps = i.prepare_computations(&r, &Intersections::default());
+
+        assert_eq!(w.reflected_color(&comps, 5), Color::new_black());
+
+        let unlit = Material::default().set_diffuse(0.).set_specular(0.);
+        let glass = Sphere::default().with_material(
+            unlit
+                .clone()
+                .set_ambient(0.)
+                .set_transparency(1.)
+                .set_refractive_index(1.5),
+        );
+        let backdrop = Plane::default()
+            .with_material(unlit.set_ambient(1.).set_color(Color::new(0., 1., 0.)))
+            .with_transform(
+                Matrix::identity()
+                    .rotation_x(std::f64::consts::PI / 2.)
+                    .translation(0., 0., 5.),
+            );
+        let w = World::new(w.light, vec![Box::new(glass), Box::new(backdrop)])
+            .set_max_reflection_depth(0)
+            .set_max_refraction_depth(5);
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+
+        assert_eq!(w.color_at(&r, 5), Color::new(0., 1., 0.));
+    }
+
     #[test]
     fn the_reflected_color_at_the_maximum_recursive_depth() {
-        let mut w = default_world();
+        let mut w = World::demo();
         let shape = Plane::default()
             .set_material(Material::default().set_reflective(0.5))
             .set_transform(Matrix::identity().translation(0., -1., 0.));
 
-        w.objects.push(Box::new(shape));
+        w.objects.push(Arc::new(shape));
 
         let r = Ray::new(
             Tuple::point(0., 0., -3.),
             Tuple::vector(0., -2.0_f64.sqrt() / 2., 2.0_f64.sqrt() / 2.),
         );
 
-        let i = w.objects[2].intersection(2.0_f64.sqrt());
+        let i = w.objects()[2].intersection(2.0_f64.sqrt());
         let comps = i.prepare_computations(&r, &Intersections::default());
         let color = w.reflected_color(&comps, 0);
 
@@ -446,8 +1323,8 @@ mod tests {
 
     #[test]
     fn the_refracted_color_with_an_opaque_surface() {
-        let w = default_world();
-        let shape = w.objects.first().unwrap();
+        let w = World::demo();
+        let shape = w.objects().first().unwrap();
         let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
         let xs = Intersections::new(vec![shape.intersection(4.), shape.intersection(6.)]);
         let comps = xs[0].prepare_computations(&r, &xs);
@@ -471,7 +1348,7 @@ mod tests {
 
         let w = World::new(Some(light), vec![Box::new(s1), Box::new(s2)]);
 
-        let shape = w.objects.first().unwrap();
+        let shape = w.objects().first().unwrap();
 
         let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
         let xs = Intersections::new(vec![shape.intersection(4.), shape.intersection(6.)]);
@@ -496,7 +1373,7 @@ mod tests {
 
         let w = World::new(Some(light), vec![Box::new(s1), Box::new(s2)]);
 
-        let shape = w.objects.first().unwrap();
+        let shape = w.objects().first().unwrap();
         let r = Ray::new(
             Tuple::point(0., 0., 2.0_f64.sqrt() / 2.),
             Tuple::vector(0., 1., 0.),
@@ -534,8 +1411,8 @@ mod tests {
 
         let w = World::new(Some(light), vec![Box::new(s1), Box::new(s2)]);
 
-        let a = w.objects.first().unwrap();
-        let b = w.objects.get(1).unwrap();
+        let a = w.objects().first().unwrap();
+        let b = w.objects().get(1).unwrap();
         let r = Ray::new(Tuple::point(0., 0., 0.1), Tuple::vector(0., 1., 0.));
         let xs = Intersections::new(vec![
             a.intersection(-0.9899),
@@ -551,8 +1428,8 @@ mod tests {
 
     #[test]
     fn shade_hit_with_a_transparent_material() {
-        let mut w = default_world();
-        let floor = Box::new(
+        let mut w = World::demo();
+        let floor = Arc::new(
             Plane::default()
                 .set_material(
                     Material::default()
@@ -570,7 +1447,7 @@ mod tests {
             .set_transform(Matrix::identity().translation(0., -3.5, -0.5));
 
         w.objects.push(floor.clone());
-        w.objects.push(Box::new(ball));
+        w.objects.push(Arc::new(ball));
 
         let r = Ray::new(
             Tuple::point(0., 0., -3.),
@@ -585,13 +1462,13 @@ mod tests {
 
     #[test]
     fn shade_hit_with_a_reflective_transparent_material() {
-        let mut w = default_world();
+        let mut w = World::demo();
         let r = Ray::new(
             Tuple::point(0., 0., -3.),
             Tuple::vector(0., -2.0_f64.sqrt() / 2., 2.0_f64.sqrt() / 2.),
         );
 
-        let floor = Box::new(
+        let floor = Arc::new(
             Plane::default()
                 .set_material(
                     Material::default()
@@ -610,7 +1487,7 @@ mod tests {
             .set_transform(Matrix::identity().translation(0., -3.5, -0.5));
 
         w.objects.push(floor.clone());
-        w.objects.push(Box::new(ball));
+        w.objects.push(Arc::new(ball));
 
         let xs = Intersections::new(vec![floor.intersection(2.0_f64.sqrt())]);
         let comps = xs[0].prepare_computations(&r, &xs);
@@ -618,4 +1495,338 @@ mod tests {
 
         assert_eq!(color, Color::new(0.93391, 0.69643, 0.69243));
     }
+
+    #[test]
+    fn a_dielectric_reflects_at_grazing_angles_without_reflectivity() {
+        let glass = Material::default()
+            .set_transparency(1.)
+            .set_reflective(0.)
+            .set_refractive_index(1.5);
+        let r = Ray::new(
+            Tuple::point(0., -0.9, -5.),
+            Tuple::vector(0., -0.1, 1.).normalize(),
+        );
+
+        let shade = |material: Material| {
+            let mut w = World::demo();
+            let floor = Plane::default()
+                .set_material(material)
+                .set_transform(Matrix::identity().translation(0., -1., 0.));
+
+            w.objects.push(Arc::new(floor.clone()));
+
+            let xs = w.intersect_world(&r);
+            let hit = xs.hit().unwrap();
+
+            assert_eq!(hit.object.id(), floor.id());
+
+            let comps = hit.prepare_computations(&r, &xs);
+            w.shade_hit(comps, 5)
+        };
+
+        let plain = shade(glass.clone());
+        let dielectric = shade(glass.set_dielectric(true));
+
+        assert!(dielectric.red() > plain.red());
+        assert!(dielectric.green() > plain.green());
+        assert!(dielectric.blue() > plain.blue());
+    }
+
+    #[test]
+    fn any_hit_before_matches_the_sorted_hit() {
+        let w = World::demo();
+        let light_position = w.light.as_ref().unwrap().position;
+
+        let points = vec![
+            Tuple::point(0., 10., 0.),
+            Tuple::point(10., -10., 10.),
+            Tuple::point(-20., 20., -20.),
+            Tuple::point(-2., 2., -2.),
+            Tuple::point(0., 0., 0.),
+            Tuple::point(5., 0.2, 3.),
+            Tuple::point(0.5, -0.5, 0.7),
+        ];
+
+        for point in points {
+            let v = light_position - point;
+            let distance = v.magnitude();
+            let r = Ray::new(point, v.normalize());
+
+            let sorted = w
+                .intersect_world(&r)
+                .hit()
+                .is_some_and(|hit| hit.t < distance);
+
+            assert_eq!(w.any_hit_before(&r, distance), sorted);
+            assert_eq!(w.is_shadowed(point), sorted);
+        }
+    }
+
+    #[test]
+    fn objects_that_do_not_cast_shadows_are_ignored_by_shadow_rays() {
+        let mut w = World::demo();
+        let p = Tuple::point(10., -10., 10.);
+
+        assert!(w.is_shadowed(p));
+
+        for object in w.objects.iter_mut() {
+            let material = object.get_material().set_casts_shadow(false);
+            Arc::get_mut(object).unwrap().set_material(material);
+        }
+
+        assert!(!w.is_shadowed(p));
+    }
+
+    #[test]
+    fn a_point_behind_an_occluder_is_lit_when_shadows_are_disabled() {
+        let w = World::demo();
+        let p = Tuple::point(10., -10., 10.);
+
+        assert!(w.is_shadowed(p));
+
+        let w = w.set_shadows_enabled(false);
+
+        assert!(!w.shadows_enabled());
+        assert!(!w.is_shadowed(p));
+    }
+
+    #[test]
+    fn iterating_over_the_world_contents() {
+        let w = World::demo();
+
+        let colors: Vec<Color> = w
+            .objects()
+            .iter()
+            .map(|object| object.get_material().get_color())
+            .collect();
+
+        assert_eq!(
+            colors,
+            vec![Color::new(0.8, 1.0, 0.6), Color::new(1., 1., 1.)]
+        );
+        assert_eq!(w.lights().len(), 1);
+        assert!(World::default().lights().is_empty());
+    }
+
+    #[test]
+    fn transparent_blockers_cast_partial_shadows() {
+        let light = Light::new(Tuple::point(0., 0., -10.), Color::new(1., 1., 1.));
+        let glass = Sphere::default().set_material(Material::default().set_transparency(0.75));
+        let opaque = Sphere::default();
+        let p = Tuple::point(0., 0., 10.);
+
+        let w = World::new(Some(light), vec![Box::new(glass)]).set_transparent_shadows(true);
+
+        assert!(w.is_shadowed(p));
+
+        let intensity = w.light_intensity_at(p);
+
+        assert!(fuzzy_equal(intensity, 0.75));
+
+        let light = Light::new(Tuple::point(0., 0., -10.), Color::new(1., 1., 1.));
+        let w = World::new(Some(light), vec![Box::new(opaque)]).set_transparent_shadows(true);
+
+        assert_eq!(w.light_intensity_at(p), 0.);
+    }
+
+    #[test]
+    fn a_light_with_a_radius_casts_soft_shadow_edges() {
+        // Just inside the shadow of the unit sphere as seen from the light
+        let p = Tuple::point(0., 2., 10.);
+        let hard = Light::new(Tuple::point(0., 0., -10.), Color::new(1., 1., 1.));
+        let w = World::new(Some(hard), vec![Box::new(Sphere::default())]);
+
+        assert_eq!(w.light_intensity_at(p), 0.);
+
+        let soft = Light::new(Tuple::point(0., 0., -10.), Color::new(1., 1., 1.)).set_radius(1.);
+        let w = World::new(Some(soft), vec![Box::new(Sphere::default())]);
+        let intensity = w.light_intensity_at(p);
+
+        assert!(intensity > 0. && intensity < 1.);
+    }
+
+    #[test]
+    fn render_bounds_clip_geometry_outside_the_box() {
+        let light = Light::new(Tuple::point(0., 0., -10.), Color::new(1., 1., 1.));
+        let left = Sphere::default().with_transform(Matrix::identity().translation(-2., 0., 0.));
+        let right = Sphere::default().with_transform(Matrix::identity().translation(2., 0., 0.));
+        let bounds = Bounds::new(
+            Tuple::point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            Tuple::point(0., f64::INFINITY, f64::INFINITY),
+        );
+        let w = World::new(Some(light), vec![Box::new(left), Box::new(right)])
+            .set_render_bounds(Some(bounds));
+
+        let r = Ray::new(Tuple::point(2., 0., -5.), Tuple::vector(0., 0., 1.));
+        assert!(w.intersect_world(&r).is_empty());
+        assert_eq!(w.color_at(&r, 5), Color::new_black());
+
+        let r = Ray::new(Tuple::point(-2., 0., -5.), Tuple::vector(0., 0., 1.));
+        assert_eq!(w.intersect_world(&r).len(), 2);
+        assert_ne!(w.color_at(&r, 5), Color::new_black());
+    }
+
+    #[test]
+    fn huge_shapes_offset_shading_points_further() {
+        let light = Light::new(Tuple::point(-10., 1., 0.), Color::new(1., 1., 1.));
+        let floor = Plane::default().with_transform(Matrix::identity().scaling_uniform(1000.));
+        let r = Ray::new(Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.));
+
+        // A hit rounded to slightly below the surface, as happens far out on
+        // a huge plane
+        let xs = Intersections::new(vec![floor.intersection(1. + 1e-4)]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        let w = World::new(Some(light), vec![Box::new(floor)]);
+
+        assert!(fuzzy_equal(comps.over_point.y, 1e-2 - 1e-4));
+        assert!(!w.is_shadowed(comps.over_point));
+
+        // unit-scale shapes shade exactly as before
+        let w = World::demo();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let comps = w.objects()[0]
+            .intersection(4.)
+            .prepare_computations(&r, &Intersections::default());
+
+        assert_eq!(comps.over_point.z, -1. - EPSILON);
+        assert_eq!(w.shade_hit(comps, 5), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn the_intersection_cap_bounds_nested_glass_shells() {
+        let shells = (1..=20).map(|i| {
+            let scale = i as f64 / 20.;
+            Box::new(Sphere::new_glass().with_transform(Matrix::identity().scaling_uniform(scale)))
+                as Box<dyn Shape>
+        });
+        let light = Light::new(Tuple::point(-10., 10., -10.), Color::new(1., 1., 1.));
+        let sky = Color::new(0.2, 0.4, 0.8);
+        let w = World::from_shapes(Some(light), shells).set_sky_gradient(sky.clone(), sky.clone());
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+
+        assert_ne!(w.color_at(&r, 5), sky);
+
+        let w = w.set_max_intersections(10);
+
+        assert_eq!(w.color_at(&r, 5), sky);
+
+        let w = World::demo();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+
+        assert_eq!(
+            w.color_at(&r, 5),
+            World::demo().set_max_intersections(100).color_at(&r, 5)
+        );
+    }
+
+    #[test]
+    fn adding_a_checker_floor() {
+        let w = World::default().with_checker_floor(Color::new_white(), Color::new_black());
+
+        assert_eq!(w.objects().len(), 1);
+
+        let floor = &w.objects()[0];
+
+        assert!(floor.as_any().is::<Plane>());
+        assert!(matches!(
+            floor.get_material().get_pattern(),
+            Some(Patterns::Checkers(_))
+        ));
+    }
+
+    #[test]
+    fn reflections_use_the_reflection_material() {
+        let light = Light::new(Tuple::point(-10., 10., -10.), Color::new(1., 1., 1.));
+        let flat = |color| {
+            Material::default()
+                .set_color(color)
+                .set_ambient(1.)
+                .set_diffuse(0.)
+                .set_specular(0.)
+        };
+        let ball = Sphere::default().set_material(
+            flat(Color::new(1., 0., 0.)).set_reflection_material(flat(Color::new(0., 1., 0.))),
+        );
+        let mirror = Plane::default()
+            .set_material(flat(Color::new_black()).set_ambient(0.).set_reflective(1.))
+            .set_transform(Matrix::identity().translation(0., -1., 0.));
+        let w = World::new(Some(light), vec![Box::new(ball), Box::new(mirror)]);
+
+        let direct = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let reflected = Ray::new(
+            Tuple::point(0., 0., -5.),
+            Tuple::vector(0., -1., 2.5).normalize(),
+        );
+
+        assert_eq!(w.color_at(&direct, 5), Color::new(1., 0., 0.));
+        assert_eq!(w.color_at(&reflected, 5), Color::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn picking_the_object_under_a_ray() {
+        let w = World::demo();
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+
+        assert_eq!(w.pick(&r), Some((w.objects()[0].id(), 4.)));
+
+        let miss = Ray::new(Tuple::point(0., 5., -5.), Tuple::vector(0., 0., 1.));
+
+        assert_eq!(w.pick(&miss), None);
+    }
+
+    #[test]
+    fn an_environment_tints_the_ambient_term() {
+        let blue = Color::new(0., 0., 1.);
+        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+
+        let plain = World::demo().color_at(&r, 5);
+        let tinted = World::demo()
+            .set_environment(Stripe::new(blue.clone(), blue).into())
+            .color_at(&r, 5);
+
+        assert_eq!(tinted.red(), plain.red());
+        assert_eq!(tinted.green(), plain.green());
+        assert!(fuzzy_equal(tinted.blue(), plain.blue() + 0.6 * 0.1));
+    }
+
+    #[test]
+    fn a_dispersive_prism_refracts_red_and_blue_apart() {
+        let prism = Cube::default().with_material(
+            Material::default()
+                .set_transparency(1.)
+                .set_refractive_index(1.5)
+                .set_dispersion(0.02),
+        );
+        let r = Ray::new(
+            Tuple::point(-2., 0., -3.),
+            Tuple::vector(1., 0., 1.).normalize(),
+        );
+        let xs = prism.intersect(&r).unwrap();
+        let xs = Intersections::new(xs);
+
+        let comps = xs[0].prepare_computations(&r, &xs);
+        let red = World::refracted_ray(&comps, -0.02).unwrap();
+        let blue = World::refracted_ray(&comps, 0.02).unwrap();
+
+        assert!((red.direction - blue.direction).magnitude() > 1e-3);
+        assert!(
+            Tuple::dot(&blue.direction, &comps.normalv)
+                < Tuple::dot(&red.direction, &comps.normalv)
+        );
+    }
+
+    #[test]
+    fn the_bounds_of_a_world_skip_infinite_objects() {
+        let left = Sphere::default().with_transform(Matrix::identity().translation(-2., 0., 0.));
+        let right = Sphere::default().with_transform(Matrix::identity().translation(2., 0., 0.));
+        let w = World::new(
+            None,
+            vec![Box::new(left), Box::new(right), Box::new(Plane::default())],
+        );
+
+        let b = w.bounds();
+
+        assert_eq!(b.min, Tuple::point(-3., -1., -1.));
+        assert_eq!(b.max, Tuple::point(3., 1., 1.));
+    }
 }
@@ -0,0 +1,66 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Why a config couldn't be loaded or turned into a scene.
+#[derive(Debug)]
+pub enum LoaderError {
+    /// A required command or key is missing, e.g. the config adds no camera.
+    MissingKey(String),
+    /// A value is present but unusable, e.g. a non-invertible transform.
+    InvalidValue(String),
+    /// A config adds a shape the loader doesn't know, e.g. `add: donut`.
+    UnknownShape(String),
+    /// A config or image file couldn't be read.
+    Io { path: PathBuf, source: io::Error },
+}
+
+impl fmt::Display for LoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoaderError::MissingKey(key) => write!(f, "{} is required", key),
+            LoaderError::InvalidValue(message) => write!(f, "{}", message),
+            LoaderError::UnknownShape(shape) => write!(f, "Unsupported shape: {}", shape),
+            LoaderError::Io { path, source } => {
+                write!(f, "Can't read {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoaderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoaderError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Keeps a `LoaderError` that was passed through `anyhow` as is, and treats
+/// anything else as an invalid value.
+impl From<anyhow::Error> for LoaderError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<LoaderError>() {
+            Ok(error) => error,
+            Err(error) => LoaderError::InvalidValue(format!("{:#}", error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LoaderError;
+
+    #[test]
+    fn converting_from_anyhow_keeps_loader_errors() {
+        let error: anyhow::Error = LoaderError::UnknownShape("donut".to_string()).into();
+
+        assert!(matches!(
+            LoaderError::from(error),
+            LoaderError::UnknownShape(shape) if shape == "donut"
+        ));
+        assert!(matches!(
+            LoaderError::from(anyhow::anyhow!("bad")),
+            LoaderError::InvalidValue(message) if message == "bad"
+        ));
+    }
+}
@@ -0,0 +1,496 @@
+use ray_tracer::{
+    camera::{Camera, Projection},
+    color::Color,
+    light::Light,
+    material::{Material, SpecularModel},
+    matrix::Matrix,
+    patterns::{Pattern, Patterns},
+    shapes::{
+        cone::Cone, cube::Cube, cylinder::Cylinder, plane::Plane, sphere::Sphere,
+        triangle::Triangle, Shape,
+    },
+    tuple::Tuple,
+};
+use serde_yaml::{Mapping, Value};
+
+use crate::{LoaderError, RenderSettings, Scene};
+
+/// Serialize a scene into the loader's YAML command format, so that it can be
+/// read back with `parse_config`. Shapes and patterns the format can't
+/// describe, such as grids or image textures, are an error rather than being
+/// left out.
+pub fn to_config(scene: &Scene) -> Result<Value, LoaderError> {
+    let mut commands = vec![camera_to_config(&scene.camera)];
+
+    if scene.settings != RenderSettings::default() {
+        commands.push(render_settings_to_config(&scene.settings));
+    }
+
+    if let Some(light) = scene.world.light() {
+        commands.push(light_to_config(light));
+    }
+
+    for object in scene.world.objects() {
+        commands.push(shape_to_config(object.as_ref())?);
+    }
+
+    Ok(Value::Sequence(commands))
+}
+
+fn not_exportable(what: &str) -> LoaderError {
+    LoaderError::InvalidValue(format!("A {} can't be exported to a config", what))
+}
+
+fn insert(mapping: &mut Mapping, key: &str, value: impl Into<Value>) {
+    mapping.insert(Value::String(key.to_string()), value.into());
+}
+
+fn tuple_to_value(tuple: Tuple) -> Value {
+    vec![tuple.x, tuple.y, tuple.z].into()
+}
+
+fn color_to_value(color: &Color) -> Value {
+    vec![color.red(), color.green(), color.blue()].into()
+}
+
+fn transform_to_value(transform: Matrix<4>) -> Value {
+    let mut command: Vec<Value> = vec!["matrix".into()];
+
+    for row in 0..4 {
+        for col in 0..4 {
+            command.push(transform[row][col].into());
+        }
+    }
+
+    Value::Sequence(vec![Value::Sequence(command)])
+}
+
+fn camera_to_config(camera: &Camera) -> Value {
+    let transform = camera.transform();
+    let from = transform.inverse() * Tuple::point(0., 0., 0.);
+    let forward = -Tuple::vector(transform[2][0], transform[2][1], transform[2][2]);
+    let true_up = Tuple::vector(transform[1][0], transform[1][1], transform[1][2]);
+
+    // `view_transform` does not normalize `left`, so its length encodes the angle
+    // between forward and the original up vector. Restore that angle so the
+    // camera re-parses into the very same transform.
+    let up = true_up + forward * (1. - true_up.magnitude_squared()).max(0.).sqrt();
+    let to = from + forward;
+
+    let mut mapping = Mapping::new();
+
+    insert(&mut mapping, "add", "camera");
+    insert(&mut mapping, "width", camera.hsize() as u64);
+    insert(&mut mapping, "height", camera.vsize() as u64);
+    insert(&mut mapping, "field-of-view", camera.field_of_view());
+
+    if let Projection::Orthographic(scale) = camera.projection() {
+        insert(&mut mapping, "type", "orthographic");
+        insert(&mut mapping, "scale", scale);
+    }
+
+    insert(&mut mapping, "from", tuple_to_value(from));
+    insert(&mut mapping, "to", tuple_to_value(to));
+    insert(&mut mapping, "up", tuple_to_value(up));
+    insert(&mut mapping, "antialiasing", camera.antialiasing() as u64);
+    insert(&mut mapping, "aperture", camera.aperture());
+    insert(&mut mapping, "focal-distance", camera.focal_distance());
+    insert(&mut mapping, "max-depth", camera.max_depth() as u64);
+
+    Value::Mapping(mapping)
+}
+
+fn light_to_config(light: &Light) -> Value {
+    let mut mapping = Mapping::new();
+
+    insert(&mut mapping, "add", "light");
+    insert(&mut mapping, "at", tuple_to_value(light.position));
+    insert(&mut mapping, "color", color_to_value(&light.color));
+    insert(&mut mapping, "intensity", light.intensity);
+
+    if !light.specular_enabled {
+        insert(&mut mapping, "specular", false);
+    }
+
+    if light.radius > 0. {
+        insert(&mut mapping, "radius", light.radius);
+    }
+
+    Value::Mapping(mapping)
+}
+
+fn render_settings_to_config(settings: &RenderSettings) -> Value {
+    let mut mapping = Mapping::new();
+
+    insert(&mut mapping, "add", "render");
+
+    if let Some(max_depth) = settings.max_depth {
+        insert(&mut mapping, "max-depth", max_depth as u64);
+    }
+
+    if let Some(samples) = settings.samples {
+        insert(&mut mapping, "samples", samples as u64);
+    }
+
+    if let Some(background) = &settings.background {
+        insert(&mut mapping, "background", color_to_value(background));
+    }
+
+    if let Some(gamma) = settings.gamma {
+        insert(&mut mapping, "gamma", gamma);
+    }
+
+    Value::Mapping(mapping)
+}
+
+fn pattern_to_config(pattern: &Patterns) -> Result<Value, LoaderError> {
+    let (pattern_type, a, b, scale) = match pattern {
+        Patterns::Stripe(p) => ("stripe", p.a(), p.b(), p.scale()),
+        Patterns::Gradient(p) => ("gradient", p.a(), p.b(), p.scale()),
+        Patterns::Ring(p) => ("ring", p.a(), p.b(), p.scale()),
+        Patterns::Checkers(p) => ("checkers", p.a(), p.b(), p.scale()),
+        Patterns::Grid(_) => return Err(not_exportable("grid pattern")),
+        Patterns::ImageTexture(_) => return Err(not_exportable("image texture")),
+        Patterns::Marble(_) => return Err(not_exportable("marble pattern")),
+        Patterns::TestPattern(_) => return Err(not_exportable("test pattern")),
+    };
+
+    let mut mapping = Mapping::new();
+
+    insert(&mut mapping, "type", pattern_type);
+    insert(
+        &mut mapping,
+        "colors",
+        vec![color_to_value(a), color_to_value(b)],
+    );
+    insert(&mut mapping, "scale", scale);
+    insert(
+        &mut mapping,
+        "transform",
+        transform_to_value(pattern.get_transform()),
+    );
+
+    Ok(Value::Mapping(mapping))
+}
+
+fn material_to_config(material: &Material) -> Result<Value, LoaderError> {
+    if material.is_masked() {
+        return Err(not_exportable("masked material"));
+    }
+    if material.get_bump_map().is_some() {
+        return Err(not_exportable("bump map"));
+    }
+
+    let mut mapping = Mapping::new();
+
+    if let Some(pattern) = material.get_pattern() {
+        insert(&mut mapping, "pattern", pattern_to_config(pattern)?);
+    }
+
+    insert(
+        &mut mapping,
+        "color",
+        color_to_value(&material.clone().get_color()),
+    );
+    insert(&mut mapping, "ambient", material.get_ambient());
+    insert(&mut mapping, "diffuse", material.get_diffuse());
+    insert(&mut mapping, "specular", material.get_specular());
+    insert(&mut mapping, "shininess", material.get_shininess());
+    insert(
+        &mut mapping,
+        "specular-model",
+        match material.get_specular_model() {
+            SpecularModel::Phong => "phong",
+            SpecularModel::BlinnPhong => "blinn-phong",
+        },
+    );
+    insert(&mut mapping, "reflective", material.get_reflective());
+    insert(
+        &mut mapping,
+        "reflective-color",
+        color_to_value(material.get_reflective_color()),
+    );
+    insert(&mut mapping, "transparency", material.get_transparency());
+    insert(
+        &mut mapping,
+        "refractive-index",
+        material.get_refractive_index(),
+    );
+    insert(&mut mapping, "dielectric", material.get_dielectric());
+    insert(&mut mapping, "flip-normals", material.get_flip_normals());
+    insert(&mut mapping, "two-sided", material.get_two_sided());
+    insert(&mut mapping, "dispersion", material.get_dispersion());
+
+    if let Some(reflection_material) = material.get_reflection_material() {
+        insert(
+            &mut mapping,
+            "reflection-material",
+            material_to_config(reflection_material)?,
+        );
+    }
+
+    Ok(Value::Mapping(mapping))
+}
+
+fn shape_variant(shape: &dyn Shape) -> Option<&'static str> {
+    let any = shape.as_any();
+
+    if any.is::<Sphere>() {
+        Some("sphere")
+    } else if any.is::<Plane>() {
+        Some("plane")
+    } else if any.is::<Cube>() {
+        Some("cube")
+    } else if any.is::<Cylinder>() {
+        Some("cylinder")
+    } else if any.is::<Cone>() {
+        Some("cone")
+    } else if any.is::<Triangle>() {
+        Some("triangle")
+    } else {
+        None
+    }
+}
+
+// Infinite bounds are the loader's default, so only finite ones are written.
+fn insert_bounds(mapping: &mut Mapping, minimum: f64, maximum: f64, closed: bool) {
+    if minimum.is_finite() {
+        insert(mapping, "minimum", minimum);
+    }
+    if maximum.is_finite() {
+        insert(mapping, "maximum", maximum);
+    }
+    insert(mapping, "closed", closed);
+}
+
+fn shape_to_config(shape: &dyn Shape) -> Result<Value, LoaderError> {
+    let mut mapping = Mapping::new();
+    let variant = shape_variant(shape).ok_or_else(|| not_exportable("shape"))?;
+
+    insert(&mut mapping, "add", variant);
+    insert(
+        &mut mapping,
+        "transform",
+        transform_to_value(shape.get_transform()),
+    );
+
+    if let Some(triangle) = shape.as_any().downcast_ref::<Triangle>() {
+        insert(&mut mapping, "p1", tuple_to_value(triangle.p1()));
+        insert(&mut mapping, "p2", tuple_to_value(triangle.p2()));
+        insert(&mut mapping, "p3", tuple_to_value(triangle.p3()));
+    }
+    if let Some(cylinder) = shape.as_any().downcast_ref::<Cylinder>() {
+        insert_bounds(
+            &mut mapping,
+            cylinder.minimum(),
+            cylinder.maximum(),
+            cylinder.closed(),
+        );
+    }
+    if let Some(cone) = shape.as_any().downcast_ref::<Cone>() {
+        insert_bounds(&mut mapping, cone.minimum(), cone.maximum(), cone.closed());
+    }
+    insert(
+        &mut mapping,
+        "material",
+        material_to_config(&shape.get_material())?,
+    );
+
+    if !shape.get_material().casts_shadow() {
+        insert(&mut mapping, "shadow", false);
+    }
+
+    if let Some(tag) = shape.get_tag() {
+        insert(&mut mapping, "tag", tag);
+    }
+
+    Ok(Value::Mapping(mapping))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_4;
+
+    use ray_tracer::{
+        camera::Camera,
+        color::Color,
+        light::Light,
+        material::Material,
+        matrix::Matrix,
+        patterns::{checkers::Checkers, grid::Grid, stripe::Stripe},
+        shapes::{cylinder::Cylinder, sphere::Sphere},
+        tuple::Tuple,
+        world::World,
+    };
+    use serde_yaml::Value;
+
+    use crate::{parse_config, to_config, LoaderError, Scene};
+
+    fn assert_same_geometry(a: &Scene, b: &Scene) {
+        assert_eq!(a.camera, b.camera);
+        assert_eq!(a.world.light(), b.world.light());
+        assert_eq!(a.world.objects().len(), b.world.objects().len());
+
+        for (x, y) in a.world.objects().iter().zip(b.world.objects().iter()) {
+            assert_eq!(x.get_transform(), y.get_transform());
+            assert_eq!(x.get_material(), y.get_material());
+        }
+    }
+
+    #[test]
+    fn to_config_serializes_a_scene_that_reparses_into_an_equal_world() {
+        let camera = Camera::new(100, 50, FRAC_PI_4)
+            .set_antialiasing(2)
+            .set_transform(Matrix::identity().view_transform(
+                Tuple::point(-3., 1., 2.5),
+                Tuple::point(0., 0.5, 0.),
+                Tuple::vector(0., 1., 0.),
+            ));
+        let light = Light::new(Tuple::point(-4.9, 4.9, -1.), Color::new(1., 1., 1.));
+        let sphere = Sphere::default()
+            .set_transform(
+                Matrix::identity()
+                    .scaling(0.4, 0.4, 0.4)
+                    .rotation_y(0.5)
+                    .translation(4.6, 0.4, 1.),
+            )
+            .set_material(
+                Material::default()
+                    .set_color(Color::new(0.8, 0.5, 0.3))
+                    .set_shininess(50.)
+                    .set_reflective(0.2)
+                    .set_pattern(
+                        Checkers::new(Color::new(0.35, 0.35, 0.35), Color::new(0.65, 0.65, 0.65))
+                            .into(),
+                    ),
+            );
+        let scene = Scene::new(camera, World::new(Some(light), vec![Box::new(sphere)]));
+
+        let config = to_config(&scene).unwrap();
+        let reparsed = parse_config(config).unwrap();
+
+        assert_same_geometry(&scene, &reparsed);
+    }
+
+    #[test]
+    fn to_config_keeps_the_pattern_scale() {
+        let camera = Camera::new(10, 10, 0.5);
+        let light = Light::new(Tuple::point(-10., 10., -10.), Color::new_white());
+        let sphere = Sphere::default().set_material(
+            Material::default().set_pattern(
+                Stripe::new(Color::new_white(), Color::new_black())
+                    .with_scale(2.5)
+                    .into(),
+            ),
+        );
+        let scene = Scene::new(camera, World::new(Some(light), vec![Box::new(sphere)]));
+
+        let reparsed = parse_config(to_config(&scene).unwrap()).unwrap();
+
+        assert_same_geometry(&scene, &reparsed);
+    }
+
+    #[test]
+    fn to_config_keeps_truncated_cylinders_and_reflection_materials() {
+        let camera = Camera::new(10, 10, 0.5);
+        let light = Light::new(Tuple::point(-10., 10., -10.), Color::new_white());
+        let cylinder = Cylinder::default()
+            .set_minimum(-0.5)
+            .set_maximum(1.5)
+            .set_closed(true)
+            .set_material(
+                Material::default()
+                    .set_reflective(0.5)
+                    .set_reflection_material(Material::default().set_color(Color::new(0., 0., 1.))),
+            );
+        let scene = Scene::new(camera, World::new(Some(light), vec![Box::new(cylinder)]));
+
+        let reparsed = parse_config(to_config(&scene).unwrap()).unwrap();
+        let reparsed_cylinder = reparsed.world.objects()[0]
+            .as_any()
+            .downcast_ref::<Cylinder>()
+            .unwrap();
+
+        assert_same_geometry(&scene, &reparsed);
+        assert_eq!(reparsed_cylinder.minimum(), -0.5);
+        assert_eq!(reparsed_cylinder.maximum(), 1.5);
+        assert!(reparsed_cylinder.closed());
+    }
+
+    #[test]
+    fn to_config_rejects_masked_materials() {
+        let camera = Camera::new(10, 10, 0.5);
+        let light = Light::new(Tuple::point(-10., 10., -10.), Color::new_white());
+        let sphere = Sphere::default().set_material(Material::masked(
+            Material::default(),
+            Material::default().set_color(Color::new_black()),
+            Stripe::new(Color::new_white(), Color::new_black()).into(),
+        ));
+        let scene = Scene::new(camera, World::new(Some(light), vec![Box::new(sphere)]));
+
+        let error = to_config(&scene).unwrap_err();
+
+        assert!(matches!(error, LoaderError::InvalidValue(_)));
+        assert!(error.to_string().contains("masked"));
+    }
+
+    #[test]
+    fn round_tripping_a_yaml_config_keeps_the_geometry() {
+        let yaml = r#"
+  - add: camera
+    width: 400
+    height: 160
+    field-of-view: 0.7854
+    from: [-3, 1, 2.5]
+    to: [0, 0.5, 0]
+    up: [0, 1, 0]
+
+  - add: light
+    at: [-4.9, 4.9, -1]
+    intensity: [1, 1, 1]
+
+  - add: plane
+    material:
+      color: [1, 1, 1]
+      ambient: 0.025
+      diffuse: 0.67
+      specular: 0
+
+  - add: cylinder
+    transform:
+      - [scale, 0.4, 0.4, 0.4]
+      - [translate, 4.6, 0.4, 1]
+    material:
+      pattern:
+        type: stripe
+        colors:
+          - [0.45, 0.45, 0.45]
+          - [0.55, 0.55, 0.55]
+        transform:
+          - [scale, 0.25, 0.25, 0.25]
+      shininess: 50"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let scene = parse_config(config).unwrap();
+        let reparsed = parse_config(to_config(&scene).unwrap()).unwrap();
+
+        assert_same_geometry(&scene, &reparsed);
+    }
+
+    #[test]
+    fn to_config_rejects_patterns_it_cant_describe() {
+        let camera = Camera::new(10, 10, 0.5);
+        let light = Light::new(Tuple::point(-10., 10., -10.), Color::new(1., 1., 1.));
+        let sphere =
+            Sphere::default()
+                .set_material(Material::default().set_pattern(
+                    Grid::new(Color::new_white(), Color::new_black(), 1., 0.1).into(),
+                ));
+        let scene = Scene::new(camera, World::new(Some(light), vec![Box::new(sphere)]));
+
+        let error = to_config(&scene).unwrap_err();
+
+        assert!(matches!(error, LoaderError::InvalidValue(_)));
+        assert!(error.to_string().contains("grid"));
+    }
+}
@@ -1,46 +1,305 @@
+use std::{
+    f64::consts::FRAC_PI_2,
+    path::{Path, PathBuf},
+};
+
 use anyhow::{Context, Result};
+use rayon::prelude::*;
 
 use ray_tracer::{
-    camera::Camera,
+    camera::{Camera, Projection},
+    canvas::Canvas,
     color::Color,
     light::Light,
     material::Material,
     matrix::Matrix,
-    shapes::{cone::Cone, cube::Cube, cylinder::Cylinder, plane::Plane, sphere::Sphere, Shape},
+    shapes::{
+        cone::Cone, cube::Cube, cylinder::Cylinder, plane::Plane, sphere::Sphere,
+        triangle::Triangle, Shape,
+    },
     tuple::Tuple,
     world::World,
 };
 use serde_yaml::{Mapping, Value};
-use utils::{get_material, get_value_by_key, get_vec_f64_from_sequence};
+use utils::{get_color, get_material, get_value_by_key, get_vec_f64_from_sequence};
 
 use crate::utils::get_transform;
 
+mod error;
+mod export;
 mod utils;
 
-pub fn parse_config(config: Value) -> Result<(Camera, World)> {
+pub use error::LoaderError;
+pub use export::to_config;
+
+/// How far an isometric preset camera is placed from its target.
+const ISOMETRIC_DISTANCE: f64 = 100.;
+
+/// Global settings from an `add: render` block. They take precedence over
+/// the camera's own settings, and unset ones leave the scene as it is.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RenderSettings {
+    /// Depth of reflection and refraction rays, see `Camera::set_max_depth`.
+    pub max_depth: Option<usize>,
+    /// Antialiasing grid size, see `Camera::set_antialiasing`.
+    pub samples: Option<usize>,
+    /// Color seen by rays that miss every object. Parsing a config sets it
+    /// as a flat sky gradient, replacing the world's sky; the environment's
+    /// ambient tint on surfaces is kept.
+    pub background: Option<Color>,
+    /// Gamma the rendered colors are encoded with.
+    pub gamma: Option<f64>,
+}
+
+/// A camera together with the world it looks at.
+pub struct Scene {
+    pub camera: Camera,
+    pub world: World,
+    pub settings: RenderSettings,
+}
+
+impl Scene {
+    pub fn new(camera: Camera, world: World) -> Self {
+        Self {
+            camera,
+            world,
+            settings: RenderSettings::default(),
+        }
+    }
+
+    pub fn with_settings(mut self, settings: RenderSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// The camera with the render settings applied.
+    pub fn render_camera(&self) -> Camera {
+        let mut camera = self.camera.clone();
+
+        if let Some(max_depth) = self.settings.max_depth {
+            camera = camera.set_max_depth(max_depth);
+        }
+
+        if let Some(samples) = self.settings.samples {
+            camera = camera.set_antialiasing(samples);
+        }
+
+        camera
+    }
+
+    /// Render the scene with its render settings applied.
+    pub fn render(&self) -> Canvas {
+        self.render_with(self.render_camera())
+    }
+
+    fn render_with(&self, camera: Camera) -> Canvas {
+        let mut canvas = camera.render(&self.world);
+
+        if let Some(gamma) = self.settings.gamma {
+            apply_gamma(&mut canvas, gamma);
+        }
+
+        canvas
+    }
+
+    /// Render one frame per camera transform into `out_dir` as numbered PNG
+    /// files (`frame_0000.png`, `frame_0001.png`, ...). Frames are rendered in
+    /// parallel. Returns the paths of the written files in frame order.
+    pub fn render_sequence(
+        &self,
+        transforms: &[Matrix<4>],
+        out_dir: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(out_dir)
+            .with_context(|| format!("Can't create output directory {}", out_dir.display()))?;
+
+        transforms
+            .par_iter()
+            .enumerate()
+            .map(|(frame, transform)| {
+                let camera = self.render_camera().set_transform(*transform);
+                let canvas = self.render_with(camera);
+                let path = out_dir.join(format!("frame_{:04}.png", frame));
+
+                image::load_from_memory(canvas.to_ppm().as_bytes())
+                    .context("Can't encode rendered frame")?
+                    .save(&path)
+                    .with_context(|| format!("Can't write frame {}", path.display()))?;
+
+                Ok(path)
+            })
+            .collect()
+    }
+}
+
+fn apply_gamma(canvas: &mut Canvas, gamma: f64) {
+    for y in 0..canvas.height {
+        for x in 0..canvas.width {
+            let color = canvas.get(x, y);
+            let encoded = Color::new(
+                color.red().max(0.).powf(1. / gamma),
+                color.green().max(0.).powf(1. / gamma),
+                color.blue().max(0.).powf(1. / gamma),
+            );
+
+            canvas.set(x, y, &encoded);
+        }
+    }
+}
+
+/// Read a YAML config from a file, replacing every `- include: <file>` entry
+/// with the entries of that file. Included and image paths are relative to
+/// the file that names them, and include cycles are an error.
+pub fn load_config(path: &Path) -> Result<Value, LoaderError> {
+    let mut entries = vec![];
+
+    load_config_into(path, &mut vec![], &mut entries)?;
+
+    Ok(Value::Sequence(entries))
+}
+
+fn load_config_into(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    entries: &mut Vec<Value>,
+) -> Result<(), LoaderError> {
+    let io_error = |source| LoaderError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let path = path.canonicalize().map_err(io_error)?;
+
+    if stack.contains(&path) {
+        return Err(LoaderError::InvalidValue(format!(
+            "Include cycle at {}",
+            path.display()
+        )));
+    }
+
+    let file = std::fs::File::open(&path).map_err(io_error)?;
+    let config: Value = serde_yaml::from_reader(file).map_err(|error| {
+        LoaderError::InvalidValue(format!("Can't parse config {}: {}", path.display(), error))
+    })?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    stack.push(path.clone());
+
+    for value in as_sequence(&config)? {
+        match value
+            .as_mapping()
+            .and_then(|c| get_value_by_key(c, "include"))
+        {
+            Some(include) => {
+                let file = include
+                    .as_str()
+                    .ok_or_else(|| invalid("include should be a file path"))?;
+
+                load_config_into(&dir.join(file), stack, entries)?;
+            }
+            None => {
+                let mut value = value.clone();
+
+                resolve_image_paths(&mut value, dir);
+                entries.push(value);
+            }
+        }
+    }
+
+    stack.pop();
+
+    Ok(())
+}
+
+/// Make the `file:` of every image pattern in `value` relative to `dir`.
+fn resolve_image_paths(value: &mut Value, dir: &Path) {
+    match value {
+        Value::Mapping(mapping) => {
+            let is_image =
+                get_value_by_key(mapping, "type").and_then(Value::as_str) == Some("image");
+
+            for (key, item) in mapping.iter_mut() {
+                match item {
+                    Value::String(file) if is_image && key.as_str() == Some("file") => {
+                        *file = dir.join(&file).to_string_lossy().into_owned();
+                    }
+                    item => resolve_image_paths(item, dir),
+                }
+            }
+        }
+        Value::Sequence(items) => {
+            for item in items {
+                resolve_image_paths(item, dir);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn parse_config(config: Value) -> Result<Scene, LoaderError> {
+    parse_config_with_camera(config, None)
+}
+
+/// Parse a scene, viewing it through the camera with the given `name:`. A
+/// config may add several cameras; without a name the first one is used.
+pub fn parse_config_with_camera(
+    config: Value,
+    camera_name: Option<&str>,
+) -> Result<Scene, LoaderError> {
+    parse_scene(config, camera_name, None)
+}
+
+/// Parse a scene, rendering at `resolution` (width, height) instead of the
+/// size given in the camera config when it is set.
+pub fn parse_config_with_overrides(
+    config: Value,
+    resolution: Option<(usize, usize)>,
+) -> Result<Scene, LoaderError> {
+    parse_scene(config, None, resolution)
+}
+
+fn parse_scene(
+    config: Value,
+    camera_name: Option<&str>,
+    resolution: Option<(usize, usize)>,
+) -> Result<Scene, LoaderError> {
     let mut camera = None;
     let mut light = None;
+    let mut settings = RenderSettings::default();
     let mut objects: Vec<Box<dyn Shape>> = vec![];
 
-    for value in config
-        .as_sequence()
-        .context("config should be a sequence")?
-    {
+    for value in as_sequence(&config)? {
         if let Value::Mapping(command) = value {
             if let Some(item) = get_value_by_key(&command, "add") {
                 if let Value::String(object) = item {
                     match object.as_str() {
-                        "camera" => {
-                            camera = get_camera_from_config(&command);
+                        "camera" if camera.is_none() => {
+                            let name = get_value_by_key(command, "name").and_then(Value::as_str);
+
+                            if camera_name.is_none() || camera_name == name {
+                                camera = Some(
+                                    get_camera_from_config(&command, resolution)
+                                        .ok_or_else(|| invalid("Can't parse camera from config"))?,
+                                );
+                            }
                         }
+                        "camera" => {}
                         "light" => {
-                            light = get_light_from_config(&command);
+                            light = Some(get_light_from_config(&command)?);
+                        }
+                        "render" => {
+                            settings = get_render_settings_from_config(command)?;
                         }
+                        _ if is_hidden(&command) => {}
                         _ => {
-                            objects.push(
-                                get_shape_from_config(&command)
-                                    .context("Can't parse shape from config")?,
-                            );
+                            if let Some(transform) = get_transform(&command)? {
+                                if !transform.is_invertible() {
+                                    return Err(invalid(
+                                        "Shape transform is not invertible, check for a zero scale",
+                                    ));
+                                }
+                            }
+
+                            objects.push(get_shape_from_config(&command)?);
                         }
                     }
                 }
@@ -48,46 +307,204 @@ pub fn parse_config(config: Value) -> Result<(Camera, World)> {
         }
     }
 
-    let world = World::new(Some(light.context("Light is required")?), objects);
+    let light = light.ok_or_else(|| LoaderError::MissingKey("Light".to_string()))?;
+    let mut world = World::new(Some(light), objects);
+
+    if let Some(background) = &settings.background {
+        world = world.set_sky_gradient(background.clone(), background.clone());
+    }
+
+    let camera = match camera_name {
+        Some(name) => camera.ok_or_else(|| LoaderError::MissingKey(format!("Camera {}", name)))?,
+        None => camera.ok_or_else(|| LoaderError::MissingKey("Camera".to_string()))?,
+    };
 
-    Ok((camera.context("Camera is required")?, world))
+    Ok(Scene::new(camera, world).with_settings(settings))
 }
 
-fn get_camera_from_config(config: &Mapping) -> Option<Camera> {
-    let width = get_value_by_key(config, "width")?.as_i64()?;
-    let height = get_value_by_key(config, "height")?.as_i64()?;
-    let field_of_view = get_value_by_key(config, "field-of-view")?.as_f64()?;
-    let from = get_vec_f64_from_sequence(config, "from")?;
-    let to = get_vec_f64_from_sequence(config, "to")?;
-    let up = get_vec_f64_from_sequence(config, "up")?;
+/// Parse a scene described in JSON. The JSON document uses the same commands
+/// and keys as the YAML format.
+pub fn parse_config_json(text: &str) -> Result<Scene, LoaderError> {
+    let config: Value = serde_json::from_str(text).map_err(|error| {
+        LoaderError::InvalidValue(format!("config should be valid JSON: {}", error))
+    })?;
 
-    Some(
-        Camera::new(width as usize, height as usize, field_of_view).set_transform(
-            Matrix::identity().view_transform(
-                Tuple::point(from[0], from[1], from[2]),
-                Tuple::point(to[0], to[1], to[2]),
-                Tuple::vector(up[0], up[1], up[2]),
-            ),
+    parse_config(config)
+}
+
+fn as_sequence(config: &Value) -> Result<&Vec<Value>, LoaderError> {
+    config
+        .as_sequence()
+        .ok_or_else(|| invalid("config should be a sequence"))
+}
+
+fn invalid(message: &str) -> LoaderError {
+    LoaderError::InvalidValue(message.to_string())
+}
+
+fn get_camera_from_config(config: &Mapping, resolution: Option<(usize, usize)>) -> Option<Camera> {
+    let (width, height) = match resolution {
+        Some(resolution) => resolution,
+        None => (
+            get_value_by_key(config, "width")?.as_u64()? as usize,
+            get_value_by_key(config, "height")?.as_u64()? as usize,
         ),
-    )
+    };
+    let projection = match get_value_by_key(config, "type") {
+        None => Projection::Perspective,
+        Some(kind) => match kind.as_str()? {
+            "perspective" => Projection::Perspective,
+            "orthographic" => Projection::Orthographic(match get_value_by_key(config, "scale") {
+                Some(scale) => scale.as_f64()?,
+                None => 1.,
+            }),
+            _ => return None,
+        },
+    };
+    // orthographic cameras don't use the field of view
+    let field_of_view = match (get_value_by_key(config, "field-of-view"), projection) {
+        (Some(field_of_view), _) => field_of_view.as_f64()?,
+        (None, Projection::Orthographic(_)) => FRAC_PI_2,
+        (None, Projection::Perspective) => return None,
+    };
+    let (from, to, up) = match get_value_by_key(config, "preset") {
+        None => (
+            get_point(config, "from")?,
+            get_point(config, "to")?,
+            get_vector(config, "up")?,
+        ),
+        Some(preset) => match preset.as_str()? {
+            // looking down at the target along the diagonal, i.e. turned 45°
+            // around y and tilted 35.264° down
+            "isometric" => {
+                let to = match get_value_by_key(config, "to") {
+                    Some(_) => get_point(config, "to")?,
+                    None => Tuple::point(0., 0., 0.),
+                };
+
+                (
+                    to + Tuple::vector(1., 1., 1.) * ISOMETRIC_DISTANCE,
+                    to,
+                    Tuple::vector(0., 1., 0.),
+                )
+            }
+            _ => return None,
+        },
+    };
+
+    let transform = Matrix::identity().try_view_transform(from, to, up)?;
+    let mut camera = Camera::new(width, height, field_of_view)
+        .set_transform(transform)
+        .set_projection(projection);
+
+    if let Some(antialiasing) = get_value_by_key(config, "antialiasing") {
+        camera = camera.set_antialiasing(antialiasing.as_u64()? as usize);
+    }
+
+    if let Some(aperture) = get_value_by_key(config, "aperture") {
+        camera = camera.set_aperture(aperture.as_f64()?);
+    }
+
+    if let Some(focal_distance) = get_value_by_key(config, "focal-distance") {
+        camera = camera.set_focal_distance(focal_distance.as_f64()?);
+    }
+
+    if let Some(max_depth) = get_value_by_key(config, "max-depth") {
+        camera = camera.set_max_depth(max_depth.as_u64()? as usize);
+    }
+
+    Some(camera)
 }
 
-fn get_light_from_config(config: &Mapping) -> Option<Light> {
-    let position = get_vec_f64_from_sequence(config, "at")?;
-    let intensity = get_vec_f64_from_sequence(config, "intensity")?;
+fn get_point(config: &Mapping, key: &str) -> Option<Tuple> {
+    Tuple::point_from_slice(&get_vec_f64_from_sequence(config, key)?).ok()
+}
 
-    Some(Light::new(
-        Tuple::point(position[0], position[1], position[2]),
-        Color::new(intensity[0], intensity[1], intensity[2]),
-    ))
+fn get_vector(config: &Mapping, key: &str) -> Option<Tuple> {
+    Tuple::vector_from_slice(&get_vec_f64_from_sequence(config, key)?).ok()
 }
 
-fn generate_shape<T: Shape + Default>(
+fn get_light_from_config(config: &Mapping) -> Result<Light, LoaderError> {
+    let position = get_point(config, "at").ok_or_else(|| invalid("Light needs an at point"))?;
+    let mut light = Light::new(position, Color::new_white());
+
+    if let Some(color) = get_value_by_key(config, "color") {
+        light = light.set_color(get_color(color, "light color")?);
+    }
+
+    // `intensity` is either a scalar brightness or, as in older configs, a color
+    match get_value_by_key(config, "intensity") {
+        Some(intensity @ Value::Sequence(_)) => {
+            light = light.set_color(get_color(intensity, "light intensity")?);
+        }
+        Some(intensity) => {
+            light = light.set_intensity(
+                intensity
+                    .as_f64()
+                    .ok_or_else(|| invalid("light intensity should be a number or a color"))?,
+            );
+        }
+        None => {}
+    }
+
+    if let Some(specular) = get_value_by_key(config, "specular") {
+        light = light.set_specular_enabled(
+            specular
+                .as_bool()
+                .ok_or_else(|| invalid("light specular should be a bool"))?,
+        );
+    }
+
+    if let Some(radius) = get_value_by_key(config, "radius") {
+        light = light.set_radius(
+            radius
+                .as_f64()
+                .ok_or_else(|| invalid("light radius should be a number"))?,
+        );
+    }
+
+    Ok(light)
+}
+
+fn get_render_settings_from_config(config: &Mapping) -> Result<RenderSettings, LoaderError> {
+    let mut settings = RenderSettings::default();
+
+    if let Some(max_depth) = get_value_by_key(config, "max-depth") {
+        let max_depth = max_depth
+            .as_u64()
+            .ok_or_else(|| invalid("max-depth should be a whole number"))?;
+
+        settings.max_depth = Some(max_depth as usize);
+    }
+
+    if let Some(samples) = get_value_by_key(config, "samples") {
+        let samples = samples
+            .as_u64()
+            .ok_or_else(|| invalid("samples should be a whole number"))?;
+
+        settings.samples = Some(samples as usize);
+    }
+
+    if let Some(background) = get_value_by_key(config, "background") {
+        settings.background = Some(get_color(background, "background")?);
+    }
+
+    if let Some(gamma) = get_value_by_key(config, "gamma") {
+        settings.gamma = Some(
+            gamma
+                .as_f64()
+                .ok_or_else(|| invalid("gamma should be a number"))?,
+        );
+    }
+
+    Ok(settings)
+}
+
+fn generate_shape<T: Shape>(
+    mut shape: T,
     transform: Option<Matrix<4>>,
     material: Option<Material>,
 ) -> T {
-    let mut shape = T::default();
-
     if let Some(transform) = transform {
         shape.set_transform(transform);
     }
@@ -99,32 +516,151 @@ fn generate_shape<T: Shape + Default>(
     shape
 }
 
-fn get_shape_from_config(config: &Mapping) -> Option<Box<dyn Shape>> {
-    let variant = get_value_by_key(config, "add")?.as_str()?;
-    let transform = get_transform(config);
-    let material = get_material(config);
-
-    let shape: Option<Box<dyn Shape>> = match variant {
-        "sphere" => Some(Box::new(generate_shape::<Sphere>(transform, material))),
-        "plane" => Some(Box::new(generate_shape::<Plane>(transform, material))),
-        "cube" => Some(Box::new(generate_shape::<Cube>(transform, material))),
-        "cylinder" => Some(Box::new(generate_shape::<Cylinder>(transform, material))),
-        "cone" => Some(Box::new(generate_shape::<Cone>(transform, material))),
-        _ => {
-            println!("miss variant: {}", variant);
-            None
+/// The `minimum:`, `maximum:` and `closed:` keys of a cylinder or cone,
+/// unbounded and open by default.
+fn get_bounds(config: &Mapping) -> Result<(f64, f64, bool), LoaderError> {
+    let bound = |key: &str, default: f64| match get_value_by_key(config, key) {
+        Some(value) => value
+            .as_f64()
+            .ok_or_else(|| invalid(&format!("{} should be a number", key))),
+        None => Ok(default),
+    };
+    let closed = match get_value_by_key(config, "closed") {
+        Some(value) => value
+            .as_bool()
+            .ok_or_else(|| invalid("closed should be a bool"))?,
+        None => false,
+    };
+
+    Ok((
+        bound("minimum", f64::NEG_INFINITY)?,
+        bound("maximum", f64::INFINITY)?,
+        closed,
+    ))
+}
+
+/// The transform turning the unit cube into the box between the `min:` and
+/// `max:` corners.
+fn get_box_transform(config: &Mapping) -> Option<Matrix<4>> {
+    let min = get_point(config, "min")?;
+    let max = get_point(config, "max")?;
+    let half_size = (max - min) * 0.5;
+
+    Some(
+        Matrix::identity()
+            .scaling(half_size.x, half_size.y, half_size.z)
+            .translation(
+                (max.x + min.x) / 2.,
+                (max.y + min.y) / 2.,
+                (max.z + min.z) / 2.,
+            ),
+    )
+}
+
+fn get_triangle_from_config(config: &Mapping) -> Option<Triangle> {
+    Some(Triangle::new(
+        get_point(config, "p1")?,
+        get_point(config, "p2")?,
+        get_point(config, "p3")?,
+    ))
+}
+
+fn is_hidden(config: &Mapping) -> bool {
+    get_value_by_key(config, "hidden")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+fn get_shape_from_config(config: &Mapping) -> Result<Box<dyn Shape>, LoaderError> {
+    let variant = get_value_by_key(config, "add")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("Shape should name its kind with add"))?;
+    let transform = get_transform(config)?;
+    let mut material = get_material(config)?;
+
+    if let Some(shadow) = get_value_by_key(config, "shadow") {
+        material = Some(
+            material.unwrap_or_default().set_casts_shadow(
+                shadow
+                    .as_bool()
+                    .ok_or_else(|| invalid("shadow should be a bool"))?,
+            ),
+        );
+    }
+
+    let mut shape: Box<dyn Shape> = match variant {
+        "sphere" => Box::new(generate_shape(Sphere::default(), transform, material)),
+        "plane" => Box::new(generate_shape(Plane::default(), transform, material)),
+        "cube" => Box::new(generate_shape(Cube::default(), transform, material)),
+        "box" => {
+            let corners = get_box_transform(config)
+                .ok_or_else(|| invalid("Box needs min and max corners"))?;
+            let transform = transform.unwrap_or_else(Matrix::identity) * corners;
+
+            if !transform.is_invertible() {
+                return Err(invalid(
+                    "Box transform is not invertible, check for equal min and max coordinates",
+                ));
+            }
+
+            Box::new(generate_shape(Cube::default(), Some(transform), material))
+        }
+        "cylinder" => {
+            let (minimum, maximum, closed) = get_bounds(config)?;
+            let cylinder = Cylinder::default()
+                .set_minimum(minimum)
+                .set_maximum(maximum)
+                .set_closed(closed);
+
+            Box::new(generate_shape(cylinder, transform, material))
+        }
+        "cone" => {
+            let (minimum, maximum, closed) = get_bounds(config)?;
+            let cone = Cone::default()
+                .set_minimum(minimum)
+                .set_maximum(maximum)
+                .set_closed(closed);
+
+            Box::new(generate_shape(cone, transform, material))
         }
+        "triangle" => Box::new(generate_shape(
+            get_triangle_from_config(config)
+                .ok_or_else(|| invalid("Triangle needs p1, p2 and p3 points"))?,
+            transform,
+            material,
+        )),
+        _ => return Err(LoaderError::UnknownShape(variant.to_string())),
     };
 
-    shape
+    if let Some(tag) = get_value_by_key(config, "tag") {
+        shape.set_tag(Some(
+            tag.as_str()
+                .ok_or_else(|| invalid("tag should be a string"))?
+                .to_string(),
+        ));
+    }
+
+    Ok(shape)
 }
 
 #[cfg(test)]
 mod tests {
-    use ray_tracer::{camera::Camera, color::Color, light::Light, matrix::Matrix, tuple::Tuple};
+    use ray_tracer::{
+        camera::{Camera, Projection},
+        color::Color,
+        light::Light,
+        matrix::Matrix,
+        patterns::Patterns,
+        shapes::{cube::Cube, triangle::Triangle},
+        tuple::Tuple,
+    };
     use serde_yaml::Value;
 
-    use crate::{get_camera_from_config, parse_config};
+    use crate::{
+        get_camera_from_config, get_light_from_config, load_config, parse_config,
+        parse_config_json, parse_config_with_camera, parse_config_with_overrides, LoaderError,
+        RenderSettings, Scene,
+    };
 
     #[test]
     fn parse_config_should_return_camera_and_world() {
@@ -165,7 +701,7 @@ mod tests {
       shininess: 50"#;
 
         let config: Value = serde_yaml::from_str(yaml).unwrap();
-        let (camera, world) = parse_config(config).unwrap();
+        let Scene { camera, world, .. } = parse_config(config).unwrap();
 
         let expected_camera =
             Camera::new(400, 160, 0.7854).set_transform(Matrix::identity().view_transform(
@@ -181,6 +717,406 @@ mod tests {
         assert_eq!(world.objects().len(), 3);
     }
 
+    #[test]
+    fn parse_config_json_matches_the_equivalent_yaml() {
+        let yaml = r#"
+  - add: camera
+    width: 400
+    height: 160
+    field-of-view: 0.7854
+    from: [-3, 1, 2.5]
+    to: [0, 0.5, 0]
+    up: [0, 1, 0]
+
+  - add: light
+    at: [-4.9, 4.9, -1]
+    intensity: [1, 1, 1]
+
+  - add: plane
+    material:
+      color: [1, 1, 1]
+      specular: 0
+
+  - add: sphere
+    transform:
+      - [scale, 0.4, 0.4, 0.4]
+      - [translate, 4.6, 0.4, 1]
+    material:
+      color: [0.8, 0.5, 0.3]
+      shininess: 50"#;
+
+        let json = r#"[
+  {
+    "add": "camera",
+    "width": 400,
+    "height": 160,
+    "field-of-view": 0.7854,
+    "from": [-3, 1, 2.5],
+    "to": [0, 0.5, 0],
+    "up": [0, 1, 0]
+  },
+  { "add": "light", "at": [-4.9, 4.9, -1], "intensity": [1, 1, 1] },
+  { "add": "plane", "material": { "color": [1, 1, 1], "specular": 0 } },
+  {
+    "add": "sphere",
+    "transform": [["scale", 0.4, 0.4, 0.4], ["translate", 4.6, 0.4, 1]],
+    "material": { "color": [0.8, 0.5, 0.3], "shininess": 50 }
+  }
+]"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let from_yaml = parse_config(config).unwrap();
+        let from_json = parse_config_json(json).unwrap();
+
+        assert_eq!(from_json.camera, from_yaml.camera);
+        assert_eq!(from_json.world.light(), from_yaml.world.light());
+        assert_eq!(
+            from_json.world.objects().len(),
+            from_yaml.world.objects().len()
+        );
+    }
+
+    #[test]
+    fn hidden_shapes_are_skipped() {
+        let yaml = r#"
+  - add: camera
+    width: 40
+    height: 16
+    field-of-view: 0.7854
+    from: [-3, 1, 2.5]
+    to: [0, 0.5, 0]
+    up: [0, 1, 0]
+
+  - add: light
+    at: [-4.9, 4.9, -1]
+    intensity: [1, 1, 1]
+
+  - add: sphere
+    hidden: true
+
+  - add: sphere
+    shadow: false"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let Scene { world, .. } = parse_config(config).unwrap();
+
+        assert_eq!(world.objects().len(), 1);
+        assert!(!world.objects()[0].get_material().casts_shadow());
+    }
+
+    #[test]
+    fn get_camera_should_read_quality_settings() {
+        let yaml = r#"
+add: camera
+width: 400
+height: 160
+field-of-view: 0.7854
+from: [-3, 1, 2.5]
+to: [0, 0.5, 0]
+up: [0, 1, 0]
+antialiasing: 4
+aperture: 0.1
+focal-distance: 3.5
+max-depth: 6"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let camera = get_camera_from_config(config.as_mapping().unwrap(), None).unwrap();
+
+        assert_eq!(camera.samples(), 16);
+        assert_eq!(camera.aperture(), 0.1);
+        assert_eq!(camera.focal_distance(), 3.5);
+        assert_eq!(camera.max_depth(), 6);
+    }
+
+    #[test]
+    fn parse_config_with_overrides_replaces_the_resolution() {
+        let yaml = r#"
+  - add: camera
+    width: 400
+    height: 160
+    field-of-view: 0.7854
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+  - add: light
+    at: [-10, 10, -10]
+    intensity: [1, 1, 1]"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let Scene { camera, .. } = parse_config_with_overrides(config, Some((100, 50))).unwrap();
+
+        assert_eq!(camera.hsize(), 100);
+        assert_eq!(camera.vsize(), 50);
+    }
+
+    #[test]
+    fn get_camera_reads_orthographic_and_isometric_settings() {
+        let yaml = r#"
+add: camera
+width: 40
+height: 20
+type: orthographic
+scale: 5
+preset: isometric"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let camera = get_camera_from_config(config.as_mapping().unwrap(), None).unwrap();
+
+        assert_eq!(camera.projection(), Projection::Orthographic(5.));
+        assert_eq!(
+            camera.transform(),
+            Matrix::identity().view_transform(
+                Tuple::point(100., 100., 100.),
+                Tuple::point(0., 0., 0.),
+                Tuple::vector(0., 1., 0.),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_config_with_camera_selects_a_camera_by_name() {
+        let yaml = r#"
+  - add: camera
+    name: main
+    width: 40
+    height: 16
+    field-of-view: 0.8
+    from: [-3, 1, 2.5]
+    to: [0, 0.5, 0]
+    up: [0, 1, 0]
+
+  - add: camera
+    name: detail
+    width: 40
+    height: 16
+    field-of-view: 0.5
+    from: [0, 2, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+
+  - add: light
+    at: [-4.9, 4.9, -1]
+    intensity: [1, 1, 1]"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+
+        let detail = parse_config_with_camera(config.clone(), Some("detail")).unwrap();
+        let first = parse_config(config.clone()).unwrap();
+
+        assert_eq!(
+            detail.camera.transform(),
+            Matrix::identity().view_transform(
+                Tuple::point(0., 2., -5.),
+                Tuple::point(0., 0., 0.),
+                Tuple::vector(0., 1., 0.),
+            )
+        );
+        assert_eq!(first.camera.field_of_view(), 0.8);
+        assert!(parse_config_with_camera(config, Some("missing")).is_err());
+    }
+
+    #[test]
+    fn get_light_should_read_color_and_scalar_intensity() {
+        let yaml = r#"
+add: light
+at: [-4.9, 4.9, -1]
+color: [1, 0.5, 0.5]
+intensity: 0.5"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let light = get_light_from_config(config.as_mapping().unwrap()).unwrap();
+
+        assert_eq!(light.color, Color::new(1., 0.5, 0.5));
+        assert_eq!(light.effective_color(), Color::new(0.5, 0.25, 0.25));
+    }
+
+    #[test]
+    fn parse_config_rejects_short_colors() {
+        let parse = |yaml: &str| {
+            parse_config(serde_yaml::from_str(yaml).unwrap())
+                .err()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            parse("- add: light\n  at: [0, 0, 0]\n  color: [1, 1]\n"),
+            "light color should be [red, green, blue] but has 2 values"
+        );
+        assert_eq!(
+            parse("- add: light\n  at: [0, 0, 0]\n  intensity: [1]\n"),
+            "light intensity should be [red, green, blue] but has 1 values"
+        );
+        assert_eq!(
+            parse("- add: light\n  at: [0, 0, 0]\n- add: sphere\n  material:\n    reflective-color: [1, 1]\n"),
+            "material reflective-color should be [red, green, blue] but has 2 values"
+        );
+        assert_eq!(
+            parse("- add: light\n  at: [0, 0, 0]\n- add: sphere\n  material:\n    pattern:\n      type: stripe\n      colors: [[1, 1, 1], [0, 0]]\n"),
+            "pattern color should be [red, green, blue] but has 2 values"
+        );
+    }
+
+    #[test]
+    fn parse_config_rejects_a_zero_scale() {
+        let yaml = r#"
+  - add: camera
+    width: 40
+    height: 16
+    field-of-view: 0.7854
+    from: [-3, 1, 2.5]
+    to: [0, 0.5, 0]
+    up: [0, 1, 0]
+
+  - add: light
+    at: [-4.9, 4.9, -1]
+    intensity: [1, 1, 1]
+
+  - add: sphere
+    transform:
+      - [scale, 1, 0, 1]"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let error = parse_config(config).err().unwrap();
+
+        assert!(matches!(error, LoaderError::InvalidValue(_)));
+        assert!(error.to_string().contains("not invertible"));
+    }
+
+    #[test]
+    fn parse_config_reads_render_settings() {
+        let yaml = r#"
+  - add: camera
+    width: 4
+    height: 2
+    field-of-view: 0.7854
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+    max-depth: 2
+
+  - add: render
+    max-depth: 10
+    background: [0.2, 0.3, 0.4]
+
+  - add: light
+    at: [-10, 10, -10]"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let scene = parse_config(config).unwrap();
+
+        assert_eq!(
+            scene.settings,
+            RenderSettings {
+                max_depth: Some(10),
+                background: Some(Color::new(0.2, 0.3, 0.4)),
+                ..RenderSettings::default()
+            }
+        );
+        assert_eq!(scene.render_camera().max_depth(), 10);
+        assert_eq!(scene.render_camera().antialiasing(), 1);
+        assert_eq!(scene.render().get(0, 0), &Color::new(0.2, 0.3, 0.4));
+        assert_eq!(
+            scene.world.sky_gradient(),
+            Some(&(Color::new(0.2, 0.3, 0.4), Color::new(0.2, 0.3, 0.4)))
+        );
+        assert!(scene.world.environment().is_none());
+    }
+
+    #[test]
+    fn parse_config_rejects_a_short_background() {
+        let yaml = "- add: render\n  background: [0.2]\n- add: light\n  at: [0, 0, 0]\n";
+
+        let error = parse_config(serde_yaml::from_str(yaml).unwrap())
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "background should be [red, green, blue] but has 1 values"
+        );
+    }
+
+    #[test]
+    fn parse_config_requires_a_light() {
+        let yaml = r#"
+  - add: camera
+    width: 40
+    height: 16
+    field-of-view: 0.7854
+    from: [-3, 1, 2.5]
+    to: [0, 0.5, 0]
+    up: [0, 1, 0]"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let error = parse_config(config).err().unwrap();
+
+        assert!(matches!(error, LoaderError::MissingKey(key) if key == "Light"));
+    }
+
+    #[test]
+    fn load_config_reports_missing_files_as_io_errors() {
+        let path = std::env::temp_dir().join("ray_tracer_loader_missing.yaml");
+        let error = load_config(&path).unwrap_err();
+
+        assert!(matches!(error, LoaderError::Io { path: p, .. } if p == path));
+    }
+
+    #[test]
+    fn parse_config_json_rejects_invalid_json() {
+        assert!(parse_config_json("[{").is_err());
+    }
+
+    #[test]
+    fn render_sequence_writes_one_png_per_transform() {
+        let yaml = r#"
+  - add: camera
+    width: 8
+    height: 8
+    field-of-view: 0.7854
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+
+  - add: light
+    at: [-10, 10, -10]
+    intensity: [1, 1, 1]
+
+  - add: sphere
+    material:
+      color: [1, 0.2, 0.2]"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let scene = parse_config(config).unwrap();
+        let out_dir = std::env::temp_dir().join("ray_tracer_loader_render_sequence");
+
+        let transforms = [
+            Matrix::identity().view_transform(
+                Tuple::point(0., 0., -5.),
+                Tuple::point(0., 0., 0.),
+                Tuple::vector(0., 1., 0.),
+            ),
+            Matrix::identity().view_transform(
+                Tuple::point(0., 0., -5.),
+                Tuple::point(0., 5., 0.),
+                Tuple::vector(0., 1., 0.),
+            ),
+        ];
+
+        let paths = scene.render_sequence(&transforms, &out_dir).unwrap();
+
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0], out_dir.join("frame_0000.png"));
+        assert_eq!(paths[1], out_dir.join("frame_0001.png"));
+
+        let first = std::fs::read(&paths[0]).unwrap();
+        let second = std::fs::read(&paths[1]).unwrap();
+
+        assert_ne!(first, second);
+
+        std::fs::remove_dir_all(&out_dir).unwrap();
+    }
+
     #[test]
     fn get_camera_should_return_a_camera_from_config() {
         let yaml = r#"
@@ -195,7 +1131,7 @@ up: [0, 1, 0]"#;
         let config: Value = serde_yaml::from_str(yaml).unwrap();
         let camera_config = config.as_mapping().unwrap();
 
-        let result = get_camera_from_config(camera_config);
+        let result = get_camera_from_config(camera_config, None);
 
         assert_eq!(
             result,
@@ -208,4 +1144,192 @@ up: [0, 1, 0]"#;
             )
         );
     }
+
+    #[test]
+    fn parse_config_reads_shape_tags() {
+        let yaml = r#"
+  - add: camera
+    width: 10
+    height: 10
+    field-of-view: 0.7854
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+
+  - add: light
+    at: [-10, 10, -10]
+    intensity: [1, 1, 1]
+
+  - add: sphere
+    tag: hero
+
+  - add: plane"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let scene = parse_config(config).unwrap();
+
+        assert_eq!(scene.world.objects()[0].get_tag(), Some("hero"));
+        assert_eq!(scene.world.objects()[1].get_tag(), None);
+    }
+
+    #[test]
+    fn parse_config_reads_triangles_and_boxes() {
+        let yaml = r#"
+  - add: camera
+    width: 10
+    height: 10
+    field-of-view: 0.7854
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+
+  - add: light
+    at: [-10, 10, -10]
+    intensity: [1, 1, 1]
+
+  - add: triangle
+    p1: [0, 1, 0]
+    p2: [-1, 0, 0]
+    p3: [1, 0, 0]
+
+  - add: box
+    min: [0, 0, 0]
+    max: [2, 4, 6]"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let scene = parse_config(config).unwrap();
+        let triangle = scene.world.objects()[0]
+            .as_any()
+            .downcast_ref::<Triangle>()
+            .unwrap();
+        let cube = &scene.world.objects()[1];
+
+        assert_eq!(triangle.p1(), Tuple::point(0., 1., 0.));
+        assert_eq!(triangle.p2(), Tuple::point(-1., 0., 0.));
+        assert_eq!(triangle.p3(), Tuple::point(1., 0., 0.));
+        assert!(cube.as_any().is::<Cube>());
+        assert_eq!(cube.parent_space_bounds().min, Tuple::point(0., 0., 0.));
+        assert_eq!(cube.parent_space_bounds().max, Tuple::point(2., 4., 6.));
+    }
+
+    #[test]
+    fn parse_config_rejects_flat_and_malformed_boxes() {
+        let parse_box = |corners: &str| {
+            let yaml = format!("- add: light\n  at: [0, 0, 0]\n- add: box\n{}", corners);
+
+            parse_config(serde_yaml::from_str(&yaml).unwrap())
+                .err()
+                .unwrap()
+        };
+
+        let flat = parse_box("  min: [0, 0, 0]\n  max: [2, 0, 6]\n");
+        let short = parse_box("  min: [0, 0]\n  max: [2, 4, 6]\n");
+
+        assert!(flat
+            .to_string()
+            .starts_with("Box transform is not invertible"));
+        assert!(
+            matches!(short, LoaderError::InvalidValue(message) if message.starts_with("Box needs"))
+        );
+    }
+
+    #[test]
+    fn parse_config_reports_unsupported_shapes() {
+        let yaml = r#"
+  - add: camera
+    width: 10
+    height: 10
+    field-of-view: 0.7854
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+
+  - add: light
+    at: [-10, 10, -10]
+    intensity: [1, 1, 1]
+
+  - add: donut"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let error = parse_config(config).err().unwrap();
+
+        assert!(matches!(&error, LoaderError::UnknownShape(shape) if shape == "donut"));
+        assert!(error.to_string().contains("donut"));
+    }
+
+    #[test]
+    fn load_config_merges_included_files() {
+        let dir = std::env::temp_dir().join("ray_tracer_loader_include");
+        std::fs::create_dir_all(dir.join("rigs")).unwrap();
+        std::fs::write(
+            dir.join("rigs/light.yaml"),
+            "- add: light\n  at: [-10, 10, -10]\n  color: [1, 0.5, 0.25]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("scene.yaml"),
+            "- add: camera\n  width: 10\n  height: 10\n  field-of-view: 0.7854\n  from: [0, 0, -5]\n  to: [0, 0, 0]\n  up: [0, 1, 0]\n- include: rigs/light.yaml\n- add: sphere\n",
+        )
+        .unwrap();
+
+        let config = load_config(&dir.join("scene.yaml")).unwrap();
+        let scene = parse_config(config).unwrap();
+
+        assert_eq!(
+            scene.world.light().unwrap().color,
+            Color::new(1., 0.5, 0.25)
+        );
+        assert_eq!(scene.world.objects().len(), 1);
+    }
+
+    #[test]
+    fn load_config_resolves_image_paths_against_the_config_file() {
+        let dir = std::env::temp_dir().join("ray_tracer_loader_image_dir");
+        std::fs::create_dir_all(dir.join("textures")).unwrap();
+        image::RgbImage::from_pixel(2, 2, image::Rgb([0, 255, 0]))
+            .save(dir.join("textures/green.png"))
+            .unwrap();
+        std::fs::write(
+            dir.join("scene.yaml"),
+            "- add: camera\n  width: 10\n  height: 10\n  field-of-view: 0.5\n  from: [0, 0, -5]\n  to: [0, 0, 0]\n  up: [0, 1, 0]\n- add: light\n  at: [-10, 10, -10]\n- add: sphere\n  material:\n    pattern:\n      type: image\n      file: textures/green.png\n",
+        )
+        .unwrap();
+
+        let config = load_config(&dir.join("scene.yaml")).unwrap();
+        let scene = parse_config(config).unwrap();
+
+        match scene.world.objects()[0].get_material().get_pattern() {
+            Some(Patterns::ImageTexture(texture)) => {
+                assert_eq!(texture.canvas().get(0, 0), &Color::new(0., 1., 0.));
+            }
+            pattern => panic!("expected an image texture, got {:?}", pattern),
+        }
+    }
+
+    #[test]
+    fn parse_config_reports_unreadable_images() {
+        let path = std::env::temp_dir().join("ray_tracer_loader_missing.png");
+        let yaml = format!(
+            "- add: light\n  at: [0, 0, 0]\n- add: sphere\n  material:\n    pattern:\n      type: image\n      file: {}\n",
+            path.display()
+        );
+
+        let error = parse_config(serde_yaml::from_str(&yaml).unwrap())
+            .err()
+            .unwrap();
+
+        assert!(matches!(error, LoaderError::Io { path: p, .. } if p == path));
+    }
+
+    #[test]
+    fn load_config_rejects_include_cycles() {
+        let dir = std::env::temp_dir().join("ray_tracer_loader_include_cycle");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.yaml"), "- include: b.yaml\n").unwrap();
+        std::fs::write(dir.join("b.yaml"), "- include: a.yaml\n").unwrap();
+
+        let error = load_config(&dir.join("a.yaml")).unwrap_err();
+
+        assert!(error.to_string().starts_with("Include cycle"));
+    }
 }
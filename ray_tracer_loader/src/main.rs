@@ -1,15 +1,15 @@
-use serde_yaml::Value;
+use std::path::Path;
 
-use ray_tracer_loader::parse_config;
+use ray_tracer_loader::{load_config, parse_config_with_camera};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let f = std::fs::File::open("world.yaml")?;
+    let config = load_config(Path::new("world.yaml"))?;
 
-    let config: Value = serde_yaml::from_reader(f)?;
+    let camera_name = std::env::args().nth(1);
 
-    let (camera, world) = parse_config(config)?;
+    let scene = parse_config_with_camera(config, camera_name.as_deref())?;
 
-    let canvas = camera.render(world);
+    let canvas = scene.render();
 
     let img = image::load_from_memory(&canvas.to_ppm().as_bytes()).unwrap();
 
@@ -1,19 +1,52 @@
+use std::path::PathBuf;
+
+use image::ImageError;
 use ray_tracer::{
+    canvas::Canvas,
     color::Color,
-    material::Material,
+    material::{Material, SpecularModel},
     matrix::Matrix,
     patterns::{
-        checkers::Checkers, gradient::Gradient, ring::Ring, stripe::Stripe, Pattern, Patterns,
+        checkers::Checkers,
+        gradient::Gradient,
+        image_texture::{ImageTexture, UvMapping},
+        ring::Ring,
+        stripe::Stripe,
+        Pattern, Patterns,
     },
 };
 use serde_yaml::{Mapping, Value};
 
+use crate::{invalid, LoaderError};
+
 pub fn get_value_by_key<'a>(config: &'a Mapping, key: &str) -> Option<&'a Value> {
     config.get(&Value::String(key.to_string()))
 }
 
 pub fn as_vec_f64(sequence: &Vec<Value>) -> Option<Vec<f64>> {
-    Some(sequence.iter().map(|item| item.as_f64().unwrap()).collect())
+    sequence.iter().map(Value::as_f64).collect()
+}
+
+/// A color from `[red, green, blue]`. `key` names the value in the error.
+pub fn color_from_slice(values: &[f64], key: &str) -> Result<Color, LoaderError> {
+    match *values {
+        [red, green, blue] => Ok(Color::new(red, green, blue)),
+        _ => Err(LoaderError::InvalidValue(format!(
+            "{} should be [red, green, blue] but has {} values",
+            key,
+            values.len()
+        ))),
+    }
+}
+
+/// A color from a config value, see `color_from_slice`.
+pub fn get_color(value: &Value, key: &str) -> Result<Color, LoaderError> {
+    let values = value
+        .as_sequence()
+        .and_then(as_vec_f64)
+        .ok_or_else(|| LoaderError::InvalidValue(format!("{} should be a list of numbers", key)))?;
+
+    color_from_slice(&values, key)
 }
 
 pub fn get_vec_f64_from_sequence<'a>(config: &'a Mapping, key: &str) -> Option<Vec<f64>> {
@@ -29,14 +62,38 @@ fn get_translate_args(sequence: &Vec<Value>) -> Option<(&str, Vec<f64>)> {
     Some((trans_type, args))
 }
 
-pub fn get_transform(shape_config: &Mapping) -> Option<Matrix<4>> {
-    let transform = get_value_by_key(shape_config, "transform")?.as_sequence()?;
+/// The `transform:` list of a shape or pattern, applied in order. Each entry
+/// names an operation followed by its numbers; `matrix` takes all 16 entries
+/// in row-major order.
+pub fn get_transform(shape_config: &Mapping) -> Result<Option<Matrix<4>>, LoaderError> {
+    let transform = match get_value_by_key(shape_config, "transform") {
+        Some(transform) => transform
+            .as_sequence()
+            .ok_or_else(|| invalid("transform should be a list"))?,
+        None => return Ok(None),
+    };
 
     let mut matrix = Matrix::identity();
 
     for value in transform {
-        let sequence = value.as_sequence()?;
-        let (trans_type, args) = get_translate_args(sequence)?;
+        let (trans_type, args) = value
+            .as_sequence()
+            .and_then(get_translate_args)
+            .ok_or_else(|| invalid("transform entries should be a name followed by numbers"))?;
+        let expected = match trans_type {
+            "translate" | "scale" => 3,
+            "rotate-x" | "rotate-y" | "rotate-z" => 1,
+            "shearing" => 6,
+            "matrix" => 16,
+            _ => continue,
+        };
+
+        if args.len() != expected {
+            return Err(invalid(&format!(
+                "{} transform should have {} numbers",
+                trans_type, expected
+            )));
+        }
 
         match trans_type {
             "translate" => {
@@ -57,113 +114,223 @@ pub fn get_transform(shape_config: &Mapping) -> Option<Matrix<4>> {
             "shearing" => {
                 matrix = matrix.shearing(args[0], args[1], args[2], args[3], args[4], args[5]);
             }
+            "matrix" => {
+                let mut m = Matrix::identity();
+
+                for row in 0..4 {
+                    for col in 0..4 {
+                        m[row][col] = args[row * 4 + col];
+                    }
+                }
+
+                matrix = m * matrix;
+            }
             _ => {}
         }
     }
 
-    Some(matrix)
+    Ok(Some(matrix))
 }
 
-fn get_pattern(pattern_config: &Mapping) -> Option<Patterns> {
-    let pattern_type = get_value_by_key(pattern_config, "type")?.as_str()?;
-    let transform = get_transform(pattern_config);
-    let colors = get_value_by_key(pattern_config, "colors")?.as_sequence()?;
-    let color1 = as_vec_f64(colors[0].as_sequence()?)?;
-    let color2 = as_vec_f64(colors[1].as_sequence()?)?;
-
-    let pattern: Option<Patterns> = match pattern_type {
-        "checkers" => Some(
-            Checkers::new(
-                Color::new(color1[0], color1[1], color1[2]),
-                Color::new(color2[0], color2[1], color2[2]),
-            )
-            .into(),
-        ),
-        "gradient" => Some(
-            Gradient::new(
-                Color::new(color1[0], color1[1], color1[2]),
-                Color::new(color2[0], color2[1], color2[2]),
-            )
-            .into(),
-        ),
-        "ring" => Some(
-            Ring::new(
-                Color::new(color1[0], color1[1], color1[2]),
-                Color::new(color2[0], color2[1], color2[2]),
-            )
-            .into(),
-        ),
-        "stripe" => Some(
-            Stripe::new(
-                Color::new(color1[0], color1[1], color1[2]),
-                Color::new(color2[0], color2[1], color2[2]),
-            )
-            .into(),
-        ),
-        _ => None,
+/// Load a `type: image` pattern. The `file:` path is decoded with the
+/// `image` crate; `load_config` makes relative paths relative to the config
+/// file, otherwise they resolve against the working directory. `mapping:` is
+/// one of `spherical` (the default), `planar` or `cube`.
+fn get_image_texture(pattern_config: &Mapping) -> Result<ImageTexture, LoaderError> {
+    let file = get_value_by_key(pattern_config, "file")
+        .ok_or_else(|| LoaderError::MissingKey("Image pattern file".to_string()))?
+        .as_str()
+        .ok_or_else(|| invalid("Image pattern file should be a path"))?;
+    let mapping = match get_value_by_key(pattern_config, "mapping") {
+        None => UvMapping::Spherical,
+        Some(value) => match value.as_str() {
+            Some("spherical") => UvMapping::Spherical,
+            Some("planar") => UvMapping::Planar,
+            Some("cube") => UvMapping::Cube,
+            _ => return Err(invalid("Unknown uv mapping, use spherical, planar or cube")),
+        },
     };
 
-    let mut pattern = pattern?;
+    let image = image::open(file)
+        .map_err(|error| match error {
+            ImageError::IoError(source) => LoaderError::Io {
+                path: PathBuf::from(file),
+                source,
+            },
+            error => LoaderError::InvalidValue(format!("Can't decode image {}: {}", file, error)),
+        })?
+        .to_rgb8();
+
+    if image.width() == 0 || image.height() == 0 {
+        return Err(invalid(&format!("Image {} has no pixels", file)));
+    }
+
+    let mut canvas = Canvas::new(image.width() as usize, image.height() as usize);
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let color = Color::new(
+            pixel[0] as f64 / 255.,
+            pixel[1] as f64 / 255.,
+            pixel[2] as f64 / 255.,
+        );
+
+        canvas.set(x as usize, y as usize, &color);
+    }
+
+    Ok(ImageTexture::new(canvas, mapping))
+}
+
+fn get_pattern(pattern_config: &Mapping) -> Result<Patterns, LoaderError> {
+    let pattern_type = get_value_by_key(pattern_config, "type")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("Pattern should name its kind with type"))?;
+    let transform = get_transform(pattern_config)?;
+
+    let mut pattern = match pattern_type {
+        "image" => get_image_texture(pattern_config)?.into(),
+        _ => get_color_pattern(pattern_type, pattern_config)?,
+    };
 
     if let Some(transform) = transform {
         pattern.set_transform(transform);
     }
 
-    Some(pattern)
+    Ok(pattern)
+}
+
+/// A two-color pattern from `colors:`, repeating `scale:` times as often as
+/// by default.
+fn get_color_pattern(
+    pattern_type: &str,
+    pattern_config: &Mapping,
+) -> Result<Patterns, LoaderError> {
+    let colors = get_value_by_key(pattern_config, "colors").and_then(Value::as_sequence);
+    let (a, b) = match colors.map(Vec::as_slice) {
+        Some([a, b]) => (
+            get_color(a, "pattern color")?,
+            get_color(b, "pattern color")?,
+        ),
+        _ => return Err(invalid("pattern colors should be a list of two colors")),
+    };
+
+    let scale = match get_value_by_key(pattern_config, "scale") {
+        Some(scale) => scale
+            .as_f64()
+            .ok_or_else(|| invalid("pattern scale should be a number"))?,
+        None => 1.,
+    };
+
+    match pattern_type {
+        "checkers" => Ok(Checkers::new(a, b).with_scale(scale).into()),
+        "gradient" => Ok(Gradient::new(a, b).with_scale(scale).into()),
+        "ring" => Ok(Ring::new(a, b).with_scale(scale).into()),
+        "stripe" => Ok(Stripe::new(a, b).with_scale(scale).into()),
+        _ => Err(LoaderError::InvalidValue(format!(
+            "Unknown pattern type {}",
+            pattern_type
+        ))),
+    }
 }
 
-pub fn get_material(shape_config: &Mapping) -> Option<Material> {
-    let mapping = get_value_by_key(shape_config, "material")?.as_mapping()?;
+/// The shape's `material:`, or `None` when it has none. Unknown keys are
+/// ignored, but a known key with an unusable value is an error.
+pub fn get_material(shape_config: &Mapping) -> Result<Option<Material>, LoaderError> {
+    match get_value_by_key(shape_config, "material") {
+        Some(material) => material_from_value(material).map(Some),
+        None => Ok(None),
+    }
+}
 
+fn material_from_value(value: &Value) -> Result<Material, LoaderError> {
+    let mapping = value
+        .as_mapping()
+        .ok_or_else(|| invalid("material should be a mapping"))?;
     let mut material = Material::default();
 
     for (key, value) in mapping.iter() {
-        match key.as_str()? {
+        let key = key
+            .as_str()
+            .ok_or_else(|| invalid("material keys should be strings"))?;
+        let bad_value = || LoaderError::InvalidValue(format!("Invalid material {}", key));
+
+        match key {
             "pattern" => {
-                let pattern = get_pattern(value.as_mapping()?)?;
+                let pattern = get_pattern(value.as_mapping().ok_or_else(bad_value)?)?;
 
                 material = material.set_pattern(pattern);
             }
             "color" => {
-                let color = as_vec_f64(value.as_sequence()?)?;
-                material = material.set_color(Color::new(color[0], color[1], color[2]));
+                material = material.set_color(get_color(value, "material color")?);
             }
             "ambient" => {
-                material = material.set_ambient(value.as_f64()?);
+                material = material.set_ambient(value.as_f64().ok_or_else(bad_value)?);
             }
             "diffuse" => {
-                material = material.set_diffuse(value.as_f64()?);
+                material = material.set_diffuse(value.as_f64().ok_or_else(bad_value)?);
             }
             "specular" => {
-                material = material.set_specular(value.as_f64()?);
+                material = material.set_specular(value.as_f64().ok_or_else(bad_value)?);
             }
             "shininess" => {
-                material = material.set_shininess(value.as_f64()?);
+                material = material.set_shininess(value.as_f64().ok_or_else(bad_value)?);
+            }
+            "specular-model" => {
+                let model = match value.as_str().ok_or_else(bad_value)? {
+                    "phong" => SpecularModel::Phong,
+                    "blinn-phong" => SpecularModel::BlinnPhong,
+                    _ => return Err(bad_value()),
+                };
+                material = material.set_specular_model(model);
             }
             "reflective" => {
-                material = material.set_reflective(value.as_f64()?);
+                material = material.set_reflective(value.as_f64().ok_or_else(bad_value)?);
+            }
+            "reflective-color" => {
+                material =
+                    material.set_reflective_color(get_color(value, "material reflective-color")?);
             }
             "transparency" => {
-                material = material.set_transparency(value.as_f64()?);
+                material = material.set_transparency(value.as_f64().ok_or_else(bad_value)?);
             }
             "refractive-index" => {
-                material = material.set_refractive_index(value.as_f64()?);
+                material = material.set_refractive_index(value.as_f64().ok_or_else(bad_value)?);
+            }
+            "dielectric" => {
+                material = material.set_dielectric(value.as_bool().ok_or_else(bad_value)?);
+            }
+            "flip-normals" => {
+                material = material.set_flip_normals(value.as_bool().ok_or_else(bad_value)?);
+            }
+            "two-sided" => {
+                material = material.set_two_sided(value.as_bool().ok_or_else(bad_value)?);
+            }
+            "reflection-material" => {
+                material = material.set_reflection_material(material_from_value(value)?);
+            }
+            "dispersion" => {
+                material = material.set_dispersion(value.as_f64().ok_or_else(bad_value)?);
             }
             _ => {}
         }
     }
 
-    Some(material)
+    Ok(material)
 }
 
 #[cfg(test)]
 mod tests {
     use ray_tracer::{
-        color::Color, material::Material, matrix::Matrix, patterns::checkers::Checkers,
+        color::Color,
+        material::Material,
+        matrix::Matrix,
+        patterns::{checkers::Checkers, image_texture::UvMapping, Patterns},
     };
     use serde_yaml::Value;
 
-    use crate::utils::{get_material, get_transform, get_value_by_key, get_vec_f64_from_sequence};
+    use crate::{
+        utils::{get_material, get_transform, get_value_by_key, get_vec_f64_from_sequence},
+        LoaderError,
+    };
 
     #[test]
     fn get_value_by_key_works() {
@@ -218,7 +385,7 @@ transform:
         let config: Value = serde_yaml::from_str(yaml).unwrap();
         let config_mapping = config.as_mapping().unwrap();
 
-        let result = get_transform(config_mapping);
+        let result = get_transform(config_mapping).unwrap();
 
         assert_eq!(
             result,
@@ -239,11 +406,27 @@ transform:
         let config: Value = serde_yaml::from_str(yaml).unwrap();
         let config_mapping = config.as_mapping().unwrap();
 
-        let result = get_transform(config_mapping);
+        let result = get_transform(config_mapping).unwrap();
 
         assert!(result.is_none());
     }
 
+    #[test]
+    fn get_transform_rejects_a_matrix_with_too_few_numbers() {
+        let yaml = r#"
+add: sphere
+transform:
+  - [matrix, 1, 0, 0, 0, 0, 1, 0, 0]"#;
+
+        let config: Value = serde_yaml::from_str(yaml).unwrap();
+        let config_mapping = config.as_mapping().unwrap();
+
+        let error = get_transform(config_mapping).unwrap_err();
+
+        assert!(matches!(error, LoaderError::InvalidValue(_)));
+        assert!(error.to_string().contains("16"));
+    }
+
     #[test]
     fn get_material_works() {
         let yaml = r#"
@@ -264,7 +447,7 @@ material:
         let config: Value = serde_yaml::from_str(yaml).unwrap();
         let config_mapping = config.as_mapping().unwrap();
 
-        let result = get_material(config_mapping);
+        let result = get_material(config_mapping).unwrap();
 
         assert_eq!(
             result,
@@ -301,7 +484,7 @@ material:
         let config: Value = serde_yaml::from_str(yaml).unwrap();
         let config_mapping = config.as_mapping().unwrap();
 
-        let result = get_material(config_mapping);
+        let result = get_material(config_mapping).unwrap();
 
         assert_eq!(
             result,
@@ -316,4 +499,35 @@ material:
             )
         );
     }
+
+    #[test]
+    fn get_material_with_image_pattern_works() {
+        let path = std::env::temp_dir().join("ray_tracer_loader_image_pattern.png");
+        image::RgbImage::from_pixel(3, 2, image::Rgb([255, 0, 0]))
+            .save(&path)
+            .unwrap();
+
+        let yaml = format!(
+            r#"
+material:
+    pattern:
+        type: image
+        file: {}
+        mapping: planar"#,
+            path.display()
+        );
+
+        let config: Value = serde_yaml::from_str(&yaml).unwrap();
+        let material = get_material(config.as_mapping().unwrap()).unwrap().unwrap();
+
+        match material.get_pattern() {
+            Some(Patterns::ImageTexture(texture)) => {
+                assert_eq!(texture.canvas().width, 3);
+                assert_eq!(texture.canvas().height, 2);
+                assert_eq!(texture.mapping(), UvMapping::Planar);
+                assert_eq!(texture.canvas().get(2, 1), &Color::new(1., 0., 0.));
+            }
+            pattern => panic!("expected an image texture, got {:?}", pattern),
+        }
+    }
 }
@@ -18,8 +18,8 @@ pub fn header_input() -> Html {
 
             match config {
                 Ok(config) => match parse_config(config) {
-                    Ok((camera, world)) => {
-                        let canvas = camera.render(world);
+                    Ok(scene) => {
+                        let canvas = scene.render();
 
                         let img = image::load_from_memory(&canvas.to_ppm().as_bytes()).unwrap();
 